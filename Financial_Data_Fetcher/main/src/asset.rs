@@ -0,0 +1,403 @@
+// Assets: a named price series backed by one or more providers
+use std::error::Error;   // For error handling
+use std::sync::mpsc::Receiver; // For ticks from a streaming feed
+use chrono::DateTime;     // For printing timestamps
+use std::time::{SystemTime, UNIX_EPOCH}; // For record timestamps
+
+use crate::breaker::{BreakerState, CircuitBreaker};
+use crate::calendar::MarketCalendar;
+use crate::config::{AssetConfig, BreakerConfig, ConsensusConfig, StorageConfig};
+use crate::consensus::{self, SpikeFilter};
+use crate::fx::Fx;
+use crate::log::warning;
+use crate::providers::{self, Provider};
+use crate::storage::PriceStore;
+use crate::stream::{self, StreamEvent};
+
+/// Defines common behavior for all priceable assets
+pub trait Pricing: Send {
+    /// Display name, e.g. "Bitcoin"
+    fn name(&self) -> &str;
+
+    /// Most recent price (None if not fetched yet)
+    fn last_price(&self) -> Option<f64>;
+
+    /// Unix time `last_price` was fetched
+    fn last_updated(&self) -> Option<u64>;
+
+    /// Provider(s) that produced `last_price`
+    fn last_source(&self) -> Option<&str>;
+
+    /// When this asset should next be fetched, given the current time
+    fn next_poll(&self, now: u64) -> u64;
+
+    /// Changes the seconds between fetches, used when the config is reloaded
+    fn set_interval(&mut self, secs: u64);
+
+    /// Fetches the current price from API
+    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>>;
+
+    /// Formats the price for display in the configured currency
+    fn display(&self, fx: &mut Fx) -> String;
+
+    /// Each provider's name and how it did on the last fetch
+    fn sources(&self) -> Vec<(&str, SourceStatus)>;
+
+    /// True if a streaming feed pushes prices between polls
+    fn has_stream(&self) -> bool {
+        false
+    }
+
+    /// True if the feed delivered a price recently enough that polling can be skipped
+    fn stream_is_live(&self, _now: u64) -> bool {
+        false
+    }
+
+    /// The newest streamed price since the last call, checked like a fetched one
+    /// (None if nothing arrived)
+    fn take_streamed(&mut self) -> Option<Result<f64, Box<dyn Error>>> {
+        None
+    }
+
+    /// True once there is nothing more to fetch, e.g. a replay that reached its end
+    fn finished(&self) -> bool {
+        false
+    }
+}
+
+/// A running websocket feed and how it's doing
+struct Stream {
+    name: String,                  // e.g. "coinbase-ws"
+    events: Receiver<StreamEvent>, // Ticks and disconnects from the feed thread
+    status: SourceStatus,
+    last_tick: Option<u64>,        // Unix time of the newest tick received
+}
+
+/// Outcome of the last request to one provider
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceStatus {
+    Untried,         // Not needed (an earlier provider answered) or never fetched
+    Ok,
+    Failed(String),  // Error message from the last attempt
+    Open(u64),       // Circuit open after repeated failures, skipped until this unix time
+}
+
+/// A tracked asset with its ordered list of providers
+pub struct Asset {
+    name: String,
+    providers: Vec<Box<dyn Provider>>,
+    statuses: Vec<SourceStatus>,  // One per provider, same order
+    breakers: Vec<CircuitBreaker>, // One per provider, same order
+    consensus: Option<ConsensusConfig>, // Query several providers instead of the first that works
+    max_jump_pct: Option<f64>,    // Spike rejection threshold
+    price_range: Option<[f64; 2]>, // Plausible [min, max] price, quotes outside are a provider failure
+    interval_secs: u64,           // Seconds between fetches
+    calendar: Option<MarketCalendar>, // Only poll during these trading hours
+    extended_hours: bool,         // Count pre-market and after-hours as open
+    stream: Option<Stream>,       // Websocket feed pushing prices between polls
+    spike_filter: SpikeFilter,
+    last_price: Option<f64>,      // Most recent price (None if not fetched yet)
+    last_source: Option<String>,  // Provider(s) that produced last_price
+    last_updated: Option<u64>,    // Unix time of last_price
+}
+
+impl Asset {
+    /// Builds an asset and its providers from a config entry
+    pub fn from_config(
+        config: &AssetConfig,
+        storage: &StorageConfig,
+        breaker: &BreakerConfig,
+    ) -> Result<Asset, Box<dyn Error>> {
+        let providers = config
+            .providers
+            .iter()
+            .map(providers::build)
+            .collect::<Result<Vec<_>, _>>()?;
+        let calendar = match &config.schedule {
+            Some(name) => Some(MarketCalendar::by_name(name)?),
+            None => None,
+        };
+
+        // The feed thread starts right away and keeps reconnecting on its own
+        let stream = match &config.stream {
+            Some(stream_config) => {
+                let source = stream::build(stream_config)?;
+                let name = source.name().to_string();
+                Some(Stream {
+                    name,
+                    events: stream::spawn(&config.name, source, stream_config.url.clone()),
+                    status: SourceStatus::Untried,
+                    last_tick: None,
+                })
+            }
+            None => None,
+        };
+
+        // Pick up where the last run left off
+        let last = PriceStore::new(&config.file, storage).last_record()?;
+
+        Ok(Asset {
+            name: config.name.clone(),
+            statuses: vec![SourceStatus::Untried; providers.len()],
+            breakers: vec![CircuitBreaker::new(breaker); providers.len()],
+            providers,
+            consensus: config.consensus.clone(),
+            max_jump_pct: config.max_jump_pct,
+            price_range: config.price_range,
+            interval_secs: config.interval_secs,
+            calendar,
+            extended_hours: config.extended_hours,
+            stream,
+            spike_filter: SpikeFilter::default(),
+            last_price: last.as_ref().map(|record| record.price),
+            last_source: last.as_ref().map(|record| record.source.clone()),
+            last_updated: last.as_ref().map(|record| record.timestamp),
+        })
+    }
+
+    /// Runs a quote through the spike filter and makes it the current price
+    fn accept(&mut self, price: f64, source: String, timestamp: u64) -> Result<f64, Box<dyn Error>> {
+        // Hold back single-tick spikes before they're published
+        if let Some(max_jump_pct) = self.max_jump_pct {
+            self.spike_filter
+                .check(price, self.last_price, max_jump_pct)
+                .map_err(|reason| format!("{}: rejected {} quote: {}", self.name, source, reason))?;
+        }
+
+        self.last_price = Some(price);  // Update last known price
+        self.last_source = Some(source);
+        self.last_updated = Some(timestamp);
+        Ok(price)
+    }
+
+    /// Rejects a quote outside the configured plausible range
+    fn check_range(&self, source: &str, price: f64) -> Result<f64, Box<dyn Error>> {
+        match self.price_range {
+            Some([min, max]) if price < min || price > max => Err(format!(
+                "{} quoted {}, outside the plausible range {} to {}",
+                source, price, min, max
+            )
+            .into()),
+            _ => Ok(price),
+        }
+    }
+
+    /// Asks one provider for a quote, recording how it went.
+    /// Providers whose circuit is open are skipped without a request.
+    fn try_provider(&mut self, index: usize) -> Option<f64> {
+        let now = unix_now();
+        let before = self.breakers[index].state();
+        if !self.breakers[index].allow(now) {
+            return None;
+        }
+        self.log_breaker(index, before);
+        let before = self.breakers[index].state();
+
+        let provider = &self.providers[index];
+        let price = match provider.fetch().and_then(|price| self.check_range(provider.name(), price)) {
+            Ok(price) => {
+                self.statuses[index] = SourceStatus::Ok;
+                self.breakers[index].success();
+                Some(price)
+            }
+            Err(e) => {
+                warning!("{}: {} failed: {}", self.name, provider.name(), e);
+                self.statuses[index] = SourceStatus::Failed(e.to_string());
+                self.breakers[index].failure(now);
+                None
+            }
+        };
+        self.log_breaker(index, before);
+        price
+    }
+
+    /// Logs a provider's circuit state change, if there was one since `before`
+    fn log_breaker(&self, index: usize, before: BreakerState) {
+        let after = self.breakers[index].state();
+        if after == before {
+            return;
+        }
+        let detail = match after {
+            BreakerState::Open { until } => format!(", skipped until {}", format_unix(until)),
+            BreakerState::HalfOpen => ", sending a trial request".to_string(),
+            BreakerState::Closed => String::new(),
+        };
+        warning!("{}: {} circuit {} -> {}{}", self.name, self.providers[index].name(), before, after, detail);
+    }
+
+    /// True if the provider is currently skipped by its circuit breaker
+    fn is_open(&self, index: usize) -> bool {
+        matches!(self.breakers[index].state(), BreakerState::Open { until } if unix_now() < until)
+    }
+
+    /// Tries each provider in order, returning the first successful quote and its source
+    fn fetch_first(&mut self) -> Result<(f64, String), Box<dyn Error>> {
+        self.statuses.fill(SourceStatus::Untried);
+        let mut failures = Vec::new();
+        for index in 0..self.providers.len() {
+            let skipped = self.is_open(index);
+            match self.try_provider(index) {
+                Some(price) => return Ok((price, self.providers[index].name().to_string())),
+                None if skipped => failures.push(format!("{} (circuit open)", self.providers[index].name())),
+                None => failures.push(self.providers[index].name().to_string()),
+            }
+        }
+        Err(format!("all sources failed for {} (tried {})", self.name, failures.join(", ")).into())
+    }
+
+    /// Queries every provider and returns the median quote, flagging sources that disagree
+    fn fetch_consensus(&mut self, settings: &ConsensusConfig) -> Result<(f64, String), Box<dyn Error>> {
+        let mut quotes = Vec::new();
+        for index in 0..self.providers.len() {
+            if let Some(price) = self.try_provider(index) {
+                quotes.push((self.providers[index].name().to_string(), price));
+            }
+        }
+        if quotes.len() < settings.min_sources {
+            return Err(format!(
+                "only {} of {} required sources answered for {}",
+                quotes.len(),
+                settings.min_sources,
+                self.name
+            )
+            .into());
+        }
+
+        let prices: Vec<f64> = quotes.iter().map(|(_, price)| *price).collect();
+        let median = consensus::median(&prices);
+
+        // Sources outside the tolerance are reported and left out of the source list
+        let mut agreeing = Vec::new();
+        for (name, price) in &quotes {
+            let deviation = consensus::deviation_pct(*price, median);
+            if deviation > settings.tolerance_pct {
+                warning!(
+                    "{}: {} quoted {:.2}, {:.1}% away from consensus {:.2}",
+                    self.name, name, price, deviation, median
+                );
+            } else {
+                agreeing.push(name.as_str());
+            }
+        }
+        Ok((median, agreeing.join("+")))
+    }
+}
+
+impl Pricing for Asset {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn last_price(&self) -> Option<f64> {
+        self.last_price
+    }
+
+    fn last_updated(&self) -> Option<u64> {
+        self.last_updated
+    }
+
+    fn last_source(&self) -> Option<&str> {
+        self.last_source.as_deref()
+    }
+
+    fn next_poll(&self, now: u64) -> u64 {
+        let next = now + self.interval_secs;
+        match &self.calendar {
+            // Outside market hours, sleep until the next session opens
+            Some(calendar) => calendar.next_session_start(next, self.extended_hours),
+            None => next,
+        }
+    }
+
+    fn set_interval(&mut self, secs: u64) {
+        self.interval_secs = secs;
+    }
+
+    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>> {
+        let (price, source) = match self.consensus.clone() {
+            Some(settings) => self.fetch_consensus(&settings)?,
+            None => self.fetch_first()?,  // Plain failover
+        };
+        self.accept(price, source, unix_now())
+    }
+
+    fn display(&self, fx: &mut Fx) -> String {
+        // Convert from USD and format for the locale, default to 0.0 if None
+        format!(
+            "{}: {} (via {})",
+            self.name,
+            fx.format_usd(self.last_price.unwrap_or(0.0)),
+            self.last_source.as_deref().unwrap_or("-")
+        )
+    }
+
+    fn sources(&self) -> Vec<(&str, SourceStatus)> {
+        let mut sources: Vec<(&str, SourceStatus)> = self
+            .providers
+            .iter()
+            .zip(self.statuses.iter().zip(&self.breakers))
+            .map(|(provider, (status, breaker))| match breaker.state() {
+                // An open circuit matters more than how the last request went
+                BreakerState::Open { until } => (provider.name(), SourceStatus::Open(until)),
+                _ => (provider.name(), status.clone()),
+            })
+            .collect();
+        if let Some(stream) = &self.stream {
+            sources.insert(0, (stream.name.as_str(), stream.status.clone()));
+        }
+        sources
+    }
+
+    fn has_stream(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn stream_is_live(&self, now: u64) -> bool {
+        self.stream.as_ref().is_some_and(|stream| {
+            stream.status == SourceStatus::Ok
+                && stream.last_tick.is_some_and(|tick| now.saturating_sub(tick) < self.interval_secs)
+        })
+    }
+
+    fn take_streamed(&mut self) -> Option<Result<f64, Box<dyn Error>>> {
+        let stream = self.stream.as_mut()?;
+
+        // Ticks can arrive many times a second, only the newest one is kept
+        let mut newest = None;
+        for event in stream.events.try_iter() {
+            match event {
+                Ok(tick) => {
+                    stream.status = SourceStatus::Ok;
+                    stream.last_tick = Some(tick.timestamp);
+                    newest = Some(tick);
+                }
+                Err(reason) => stream.status = SourceStatus::Failed(reason),
+            }
+        }
+        let tick = newest?;
+        let source = stream.name.clone();
+        if let Err(e) = self.check_range(&source, tick.price) {
+            if let Some(stream) = self.stream.as_mut() {
+                stream.status = SourceStatus::Failed(e.to_string());
+            }
+            return Some(Err(format!("{}: {}", self.name, e).into()));
+        }
+        Some(self.accept(tick.price, source, tick.timestamp))
+    }
+}
+
+/// Current time as seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)  // Clock before 1970, not worth failing over
+}
+
+/// "2026-10-18 14:05:00 UTC" for a unix timestamp
+pub fn format_unix(timestamp: u64) -> String {
+    match DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => timestamp.to_string(),
+    }
+}
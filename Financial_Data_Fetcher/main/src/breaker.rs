@@ -0,0 +1,132 @@
+// Circuit breakers: stop asking a provider that keeps failing, then probe it after a cool-down
+use std::fmt;            // For printing the state
+
+use crate::config::BreakerConfig;
+
+/// Where a breaker is in its cycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakerState {
+    Closed,              // Requests go through
+    Open { until: u64 }, // Provider skipped until this unix time
+    HalfOpen,            // Cool-down over, the next request is a trial
+}
+
+impl fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "closed"),
+            BreakerState::Open { .. } => write!(f, "open"),
+            BreakerState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Consecutive-failure breaker for one provider of one asset
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,     // Failures in a row that open it (0 = never)
+    cooldown_secs: u64, // How long it stays open
+    failures: u32,      // Failures in a row so far
+    state: BreakerState,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &BreakerConfig) -> CircuitBreaker {
+        CircuitBreaker {
+            threshold: config.failure_threshold,
+            cooldown_secs: config.cooldown_secs,
+            failures: 0,
+            state: BreakerState::Closed,
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.state
+    }
+
+    /// True if a request may go out now. An open breaker whose cool-down has passed turns
+    /// half-open and lets one trial request through.
+    pub fn allow(&mut self, now: u64) -> bool {
+        match self.state {
+            BreakerState::Open { until } if now < until => false,
+            BreakerState::Open { .. } => {
+                self.state = BreakerState::HalfOpen;
+                true
+            }
+            BreakerState::Closed | BreakerState::HalfOpen => true,
+        }
+    }
+
+    /// A request succeeded: the breaker closes and the failure count starts over
+    pub fn success(&mut self) {
+        self.failures = 0;
+        self.state = BreakerState::Closed;
+    }
+
+    /// A request failed: the breaker opens at the threshold, or straight away after a failed trial
+    pub fn failure(&mut self, now: u64) {
+        self.failures = self.failures.saturating_add(1);
+        let trip = match self.state {
+            BreakerState::HalfOpen => true,
+            _ => self.threshold > 0 && self.failures >= self.threshold,
+        };
+        if trip {
+            self.state = BreakerState::Open { until: now + self.cooldown_secs };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(threshold: u32) -> CircuitBreaker {
+        CircuitBreaker::new(&BreakerConfig { failure_threshold: threshold, cooldown_secs: 60 })
+    }
+
+    #[test]
+    fn opens_at_the_threshold_and_probes_after_the_cooldown() {
+        let mut breaker = breaker(3);
+        breaker.failure(100);
+        breaker.failure(101);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.allow(101));
+
+        breaker.failure(102);
+        assert_eq!(breaker.state(), BreakerState::Open { until: 162 });
+        assert!(!breaker.allow(161));
+
+        // Cool-down over: one trial goes through, and a failed trial opens it again at once
+        assert!(breaker.allow(162));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        breaker.failure(162);
+        assert_eq!(breaker.state(), BreakerState::Open { until: 222 });
+
+        // A successful trial closes it and starts the count over
+        assert!(breaker.allow(222));
+        breaker.success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        breaker.failure(223);
+        breaker.failure(224);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let mut breaker = breaker(2);
+        breaker.failure(100);
+        breaker.success();
+        breaker.failure(101);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn threshold_zero_never_opens() {
+        let mut breaker = breaker(0);
+        for now in 0..100 {
+            breaker.failure(now);
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.allow(100));
+    }
+}
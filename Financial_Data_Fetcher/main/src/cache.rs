@@ -0,0 +1,198 @@
+// Short-lived cache of provider responses so several consumers within a few seconds share one request
+use std::collections::HashMap; // For entries by key
+use std::error::Error;   // For error handling
+use std::fmt;            // For printing the statistics
+use std::sync::Mutex;    // Shared between the fetch thread and any other caller
+use std::time::{Duration, Instant}; // For entry ages
+
+use crate::config::CacheConfig;
+
+/// All cached responses and the hit counters
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+    ttl: Duration::from_secs(0),
+    entries: None,
+    stats: Stats { hits: 0, revalidated: 0, misses: 0 },
+});
+
+struct Cache {
+    ttl: Duration,                          // How long a body is served without asking upstream
+    entries: Option<HashMap<String, Entry>>, // None until the first store (Mutex::new needs a const)
+    stats: Stats,
+}
+
+/// One cached response body
+struct Entry {
+    body: String,
+    etag: Option<String>,  // Sent back as If-None-Match once the entry is stale
+    fetched: Instant,      // When upstream last confirmed the body
+}
+
+/// Counters for the cache's effectiveness
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    pub hits: u64,        // Served from memory without a request
+    pub revalidated: u64, // Upstream answered 304 Not Modified
+    pub misses: u64,      // Full response downloaded
+}
+
+impl Stats {
+    /// Share of lookups that didn't download a body (hits and 304s), 0-100
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.revalidated + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        (self.hits + self.revalidated) as f64 / total as f64 * 100.0
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} hits, {} not modified, {} misses ({:.0}% hit rate)",
+            self.hits,
+            self.revalidated,
+            self.misses,
+            self.hit_rate()
+        )
+    }
+}
+
+/// Applies the configured TTL; call once at startup
+pub fn configure(config: &CacheConfig) {
+    lock().ttl = Duration::from_secs(config.ttl_secs);
+}
+
+/// Cache key for one quote, e.g. "coingecko:bitcoin:USD"
+pub fn key(provider: &str, symbol: &str, currency: &str) -> String {
+    format!("{}:{}:{}", provider, symbol, currency)
+}
+
+/// Returns the body for `key`, calling `request` only if there's no fresh copy.
+/// `request` gets the ETag of a stale copy (if any) to send as If-None-Match.
+pub fn fetch<F>(key: &str, request: F) -> Result<String, Box<dyn Error>>
+where
+    F: FnOnce(Option<&str>) -> Result<ureq::Response, Box<dyn Error>>,
+{
+    let etag = {
+        let mut cache = lock();
+        let ttl = cache.ttl;
+        match cache.entries.get_or_insert_with(HashMap::new).get(key) {
+            Some(entry) if entry.fetched.elapsed() < ttl => {
+                let body = entry.body.clone();
+                cache.stats.hits += 1;
+                return Ok(body);
+            }
+            Some(entry) => entry.etag.clone(),
+            None => None,
+        }
+    };
+
+    // The lock isn't held across the request, a slow provider mustn't block the others
+    let response = request(etag.as_deref())?;
+
+    let mut cache = lock();
+    let entries = cache.entries.get_or_insert_with(HashMap::new);
+    if response.status() == 304 {
+        if let Some(entry) = entries.get_mut(key) {
+            entry.fetched = Instant::now();
+            let body = entry.body.clone();
+            cache.stats.revalidated += 1;
+            return Ok(body);
+        }
+        return Err("304 Not Modified without a cached response".into());
+    }
+
+    let new_etag = response.header("ETag").map(str::to_string);
+    let body = response.into_string()?;
+    entries.insert(
+        key.to_string(),
+        Entry {
+            body: body.clone(),
+            etag: new_etag,
+            fetched: Instant::now(),
+        },
+    );
+    cache.stats.misses += 1;
+    Ok(body)
+}
+
+/// Drops a cached body, e.g. one that failed to parse
+pub fn invalidate(key: &str) {
+    if let Some(entries) = lock().entries.as_mut() {
+        entries.remove(key);
+    }
+}
+
+/// Current hit and miss counts
+pub fn stats() -> Stats {
+    lock().stats
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Cache> {
+    CACHE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A canned upstream response
+    fn response(status: &str, etag: &str, body: &str) -> Result<ureq::Response, Box<dyn Error>> {
+        Ok(format!("HTTP/1.1 {}\r\nETag: {}\r\n\r\n{}", status, etag, body).parse()?)
+    }
+
+    fn fetch_ok(key: &str, request: impl FnOnce(Option<&str>) -> Result<ureq::Response, Box<dyn Error>>) -> String {
+        fetch(key, request).unwrap()
+    }
+
+    // The cache is process-wide, so one test walks through its whole life cycle
+    #[test]
+    fn ttl_etag_revalidation_and_stats() {
+        let key = key("test", "BTC", "USD");
+        let before = stats();
+
+        // A miss downloads, a fresh copy is served without a request
+        configure(&CacheConfig { ttl_secs: 60 });
+        assert_eq!(fetch_ok(&key, |etag| {
+            assert_eq!(etag, None);
+            response("200 OK", "\"v1\"", "one")
+        }), "one");
+        assert_eq!(fetch_ok(&key, |_| panic!("fresh copy should be served")), "one");
+
+        // Once stale, the ETag goes back upstream and a 304 keeps the body
+        configure(&CacheConfig { ttl_secs: 0 });
+        assert_eq!(fetch_ok(&key, |etag| {
+            assert_eq!(etag, Some("\"v1\""));
+            response("304 Not Modified", "\"v1\"", "")
+        }), "one");
+
+        // A new body replaces the old one along with its ETag
+        assert_eq!(fetch_ok(&key, |_| response("200 OK", "\"v2\"", "two")), "two");
+        assert_eq!(fetch_ok(&key, |etag| {
+            assert_eq!(etag, Some("\"v2\""));
+            response("304 Not Modified", "\"v2\"", "")
+        }), "two");
+
+        // Invalidated entries are fetched from scratch, and a 304 then has nothing to reuse
+        invalidate(&key);
+        assert!(fetch(&key, |etag| {
+            assert_eq!(etag, None);
+            response("304 Not Modified", "\"v2\"", "")
+        })
+        .is_err());
+
+        let after = stats();
+        assert_eq!(after.hits - before.hits, 1);
+        assert_eq!(after.revalidated - before.revalidated, 2);
+        assert_eq!(after.misses - before.misses, 2);
+        configure(&CacheConfig::default());
+    }
+
+    #[test]
+    fn hit_rate_counts_hits_and_revalidations() {
+        assert_eq!(Stats::default().hit_rate(), 0.0);
+        assert_eq!(Stats { hits: 1, revalidated: 1, misses: 2 }.hit_rate(), 50.0);
+    }
+}
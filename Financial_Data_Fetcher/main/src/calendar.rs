@@ -0,0 +1,406 @@
+// Market calendar: exchange sessions, weekends, holidays and early closes
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday}; // For dates and times
+use chrono_tz::Tz;       // For exchange time zones
+use std::error::Error;   // For error handling
+
+/// How far ahead `next_open` looks before giving up (covers any holiday cluster)
+const MAX_LOOKAHEAD_DAYS: i64 = 14;
+
+/// Which part of the trading day a moment falls in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Session {
+    PreMarket,   // Extended hours before the open
+    Regular,     // Regular trading session
+    AfterHours,  // Extended hours after the close
+    Closed,      // Overnight, weekends and holidays
+}
+
+impl Session {
+    /// Short tag stored with quotes ("regular", "pre", "post", "closed")
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Session::PreMarket => "pre",
+            Session::Regular => "regular",
+            Session::AfterHours => "post",
+            Session::Closed => "closed",
+        }
+    }
+}
+
+/// A named full-day closure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: &'static str,
+}
+
+/// Trading hours of one exchange (all times are local to the exchange)
+#[derive(Debug, Clone)]
+pub struct MarketCalendar {
+    pub name: String,         // "nyse"
+    tz: Tz,                   // Exchange time zone
+    pre_open: NaiveTime,      // Pre-market session starts
+    open: NaiveTime,          // Regular session open
+    close: NaiveTime,         // Regular session close
+    early_close: NaiveTime,   // Close on half days
+    post_close: NaiveTime,    // After-hours session ends
+    early_post_close: NaiveTime, // After-hours end on half days
+}
+
+impl MarketCalendar {
+    /// Looks up a calendar by name ("nyse" or "nasdaq")
+    pub fn by_name(name: &str) -> Result<MarketCalendar, Box<dyn Error>> {
+        let time = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default();
+        match name.to_lowercase().as_str() {
+            // Both US exchanges share hours, holidays and half days
+            "nyse" | "nasdaq" => Ok(MarketCalendar {
+                name: name.to_lowercase(),
+                tz: chrono_tz::America::New_York,
+                pre_open: time(4, 0),
+                open: time(9, 30),
+                close: time(16, 0),
+                early_close: time(13, 0),
+                post_close: time(20, 0),
+                early_post_close: time(17, 0),
+            }),
+            other => Err(format!("unknown market calendar '{}'", other).into()),
+        }
+    }
+
+    /// Exchange time zone name, e.g. "America/New_York"
+    pub fn timezone(&self) -> &'static str {
+        self.tz.name()
+    }
+
+    /// Session in effect at `timestamp`
+    pub fn session_at(&self, timestamp: u64) -> Session {
+        let local = self.local(timestamp);
+        let date = local.date_naive();
+        if !self.is_trading_day(date) {
+            return Session::Closed;
+        }
+        let (close, post_close) = self.closes_on(date);
+        let time = local.time();
+        if time < self.pre_open || time >= post_close {
+            Session::Closed
+        } else if time < self.open {
+            Session::PreMarket
+        } else if time < close {
+            Session::Regular
+        } else {
+            Session::AfterHours
+        }
+    }
+
+    /// True if `timestamp` falls inside a regular trading session
+    pub fn is_open(&self, timestamp: u64) -> bool {
+        self.session_at(timestamp) == Session::Regular
+    }
+
+    /// Start of the next regular session at or after `timestamp` (itself if already open)
+    pub fn next_open(&self, timestamp: u64) -> u64 {
+        self.next_session_start(timestamp, false)
+    }
+
+    /// Next moment at or after `timestamp` when the market is open, counting
+    /// pre-market and after-hours as open if `extended` is set
+    pub fn next_session_start(&self, timestamp: u64, extended: bool) -> u64 {
+        let session = self.session_at(timestamp);
+        if session == Session::Regular || (extended && session != Session::Closed) {
+            return timestamp;
+        }
+        let start_time = if extended { self.pre_open } else { self.open };
+        let start = self.local(timestamp).date_naive();
+        for offset in 0..MAX_LOOKAHEAD_DAYS {
+            let date = start + Duration::days(offset);
+            if !self.is_trading_day(date) {
+                continue;
+            }
+            if let Some(open) = self.at(date, start_time) {
+                if open >= timestamp {
+                    return open;
+                }
+            }
+        }
+        timestamp  // Unreachable in practice, poll rather than stall
+    }
+
+    /// Weekday that isn't an exchange holiday
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            && !us_holidays(date.year()).iter().any(|holiday| holiday.date == date)
+    }
+
+    /// Regular and after-hours close times for a trading day
+    fn closes_on(&self, date: NaiveDate) -> (NaiveTime, NaiveTime) {
+        if us_early_closes(date.year()).contains(&date) {
+            (self.early_close, self.early_post_close)
+        } else {
+            (self.close, self.post_close)
+        }
+    }
+
+    /// Multi-line summary: current session, next open, and this year's holidays and half days
+    pub fn report(&self, timestamp: u64) -> String {
+        let local = self.local(timestamp);
+        let year = local.year();
+        let mut report = format!("{} ({})\n", self.name.to_uppercase(), self.timezone());
+        let status = match self.session_at(timestamp) {
+            _ if self.is_open(timestamp) => "open".to_string(),
+            Session::Closed => "closed".to_string(),
+            extended => format!("closed, {} session", extended.tag()),
+        };
+        report.push_str(&format!("  At {}: {}\n", local.format("%Y-%m-%d %H:%M %Z"), status));
+        report.push_str(&format!(
+            "  Next regular open: {}\n",
+            self.local(self.next_open(timestamp)).format("%Y-%m-%d %H:%M %Z")
+        ));
+        report.push_str(&format!(
+            "  Hours: pre {} / regular {}-{} / after-hours until {}\n",
+            self.pre_open.format("%H:%M"),
+            self.open.format("%H:%M"),
+            self.close.format("%H:%M"),
+            self.post_close.format("%H:%M")
+        ));
+        report.push_str(&format!("  Holidays {}:\n", year));
+        for holiday in us_holidays(year) {
+            report.push_str(&format!("    {} {}\n", holiday.date.format("%a %Y-%m-%d"), holiday.name));
+        }
+        report.push_str(&format!("  Early closes {} ({}):\n", year, self.early_close.format("%H:%M")));
+        for date in us_early_closes(year) {
+            report.push_str(&format!("    {}\n", date.format("%a %Y-%m-%d")));
+        }
+        report
+    }
+
+    /// `timestamp` in the exchange's time zone
+    fn local(&self, timestamp: u64) -> DateTime<Tz> {
+        let utc = DateTime::<Utc>::from_timestamp(timestamp as i64, 0).unwrap_or_default();
+        utc.with_timezone(&self.tz)
+    }
+
+    /// Unix time of a local date and time at the exchange
+    fn at(&self, date: NaiveDate, time: NaiveTime) -> Option<u64> {
+        self.tz
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(|local| local.timestamp().max(0) as u64)
+    }
+}
+
+/// Full-day NYSE/Nasdaq closures in `year`, following the exchange's observance rules
+pub fn us_holidays(year: i32) -> Vec<Holiday> {
+    let mut holidays = Vec::new();
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day);
+    let mut add = |date: Option<NaiveDate>, name| {
+        if let Some(date) = date {
+            holidays.push(Holiday { date, name });
+        }
+    };
+
+    // New Year's Day moves to Monday if on Sunday, but is not observed on the Friday before
+    if let Some(new_year) = date(1, 1) {
+        match new_year.weekday() {
+            Weekday::Sun => add(Some(new_year + Duration::days(1)), "New Year's Day"),
+            Weekday::Sat => {}
+            _ => add(Some(new_year), "New Year's Day"),
+        }
+    }
+
+    add(nth_weekday(year, 1, Weekday::Mon, 3), "Martin Luther King Jr. Day");
+    add(nth_weekday(year, 2, Weekday::Mon, 3), "Washington's Birthday");
+    add(easter(year).map(|easter| easter - Duration::days(2)), "Good Friday");
+    add(last_weekday(year, 5, Weekday::Mon), "Memorial Day");
+    if year >= 2022 {
+        add(date(6, 19).map(observed), "Juneteenth");
+    }
+    add(date(7, 4).map(observed), "Independence Day");
+    add(nth_weekday(year, 9, Weekday::Mon, 1), "Labor Day");
+    add(nth_weekday(year, 11, Weekday::Thu, 4), "Thanksgiving Day");
+    add(date(12, 25).map(observed), "Christmas Day");
+
+    // One-off closures announced by the exchange
+    for (y, month, day, name) in SPECIAL_CLOSURES {
+        if *y == year {
+            add(date(*month, *day), name);
+        }
+    }
+    holidays.sort_by_key(|holiday| holiday.date);
+    holidays
+}
+
+/// Half days (13:00 close): July 3 and Christmas Eve on Monday-Thursday, and the day after Thanksgiving
+pub fn us_early_closes(year: i32) -> Vec<NaiveDate> {
+    let mut days = Vec::new();
+    let weekday_before_holiday = |date: NaiveDate| matches!(
+        date.weekday(),
+        Weekday::Mon | Weekday::Tue | Weekday::Wed | Weekday::Thu
+    );
+    if let Some(july_3) = NaiveDate::from_ymd_opt(year, 7, 3).filter(|d| weekday_before_holiday(*d)) {
+        days.push(july_3);
+    }
+    if let Some(thanksgiving) = nth_weekday(year, 11, Weekday::Thu, 4) {
+        days.push(thanksgiving + Duration::days(1));
+    }
+    if let Some(christmas_eve) = NaiveDate::from_ymd_opt(year, 12, 24).filter(|d| weekday_before_holiday(*d)) {
+        days.push(christmas_eve);
+    }
+    days
+}
+
+/// Unscheduled closures (national days of mourning and the like)
+const SPECIAL_CLOSURES: &[(i32, u32, u32, &str)] = &[
+    (2018, 12, 5, "National Day of Mourning (George H. W. Bush)"),
+    (2025, 1, 9, "National Day of Mourning (Jimmy Carter)"),
+];
+
+/// Saturday holidays are observed on Friday, Sunday ones on Monday
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+/// The `n`th `weekday` of a month, e.g. the 3rd Monday of January
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> Option<NaiveDate> {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n)
+}
+
+/// The last `weekday` of a month, e.g. the last Monday of May
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    nth_weekday(year, month, weekday, 5).or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+/// Western Easter Sunday (anonymous Gregorian algorithm)
+fn easter(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nyse() -> MarketCalendar {
+        MarketCalendar::by_name("nyse").unwrap()
+    }
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// Unix time of a UTC date and time
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> u64 {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap().timestamp() as u64
+    }
+
+    #[test]
+    fn holidays_2026_follow_the_exchange_schedule() {
+        let dates: Vec<NaiveDate> = us_holidays(2026).iter().map(|holiday| holiday.date).collect();
+        assert_eq!(
+            dates,
+            [
+                day(2026, 1, 1),
+                day(2026, 1, 19),
+                day(2026, 2, 16),
+                day(2026, 4, 3),   // Good Friday, Easter is April 5
+                day(2026, 5, 25),
+                day(2026, 6, 19),
+                day(2026, 7, 3),   // July 4 is a Saturday
+                day(2026, 9, 7),
+                day(2026, 11, 26),
+                day(2026, 12, 25),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekend_holidays_are_observed_on_the_nearest_weekday() {
+        let table = [
+            (day(2027, 7, 5), true),    // July 4 on a Sunday moves to Monday
+            (day(2027, 12, 24), true),  // Christmas on a Saturday moves to Friday
+            (day(2027, 12, 31), false), // ...but New Year's Day on a Saturday isn't moved back
+            (day(2022, 12, 26), true),  // Christmas on a Sunday
+            (day(2021, 6, 18), false),  // No Juneteenth before 2022
+            (day(2025, 1, 9), true),    // Special closure
+        ];
+        for (date, holiday) in table {
+            assert_eq!(us_holidays(date.year()).iter().any(|h| h.date == date), holiday, "{}", date);
+        }
+    }
+
+    #[test]
+    fn easter_dates() {
+        for (year, month, date) in [(2000, 4, 23), (2019, 4, 21), (2024, 3, 31), (2025, 4, 20), (2026, 4, 5), (2038, 4, 25)] {
+            assert_eq!(easter(year), Some(day(year, month, date)), "{}", year);
+        }
+    }
+
+    #[test]
+    fn early_closes_skip_days_that_are_holidays() {
+        // July 3 2026 is the observed Independence Day, not a half day
+        assert_eq!(us_early_closes(2026), [day(2026, 11, 27), day(2026, 12, 24)]);
+        assert_eq!(us_early_closes(2025), [day(2025, 7, 3), day(2025, 11, 28), day(2025, 12, 24)]);
+    }
+
+    #[test]
+    fn sessions_on_a_normal_day_and_a_half_day() {
+        let nyse = nyse();
+        // Monday 2026-10-19, New York is UTC-4
+        let table = [
+            (utc(2026, 10, 19, 7, 59), Session::Closed),
+            (utc(2026, 10, 19, 8, 0), Session::PreMarket),
+            (utc(2026, 10, 19, 13, 30), Session::Regular),
+            (utc(2026, 10, 19, 19, 59), Session::Regular),
+            (utc(2026, 10, 19, 20, 0), Session::AfterHours),
+            (utc(2026, 10, 20, 0, 0), Session::Closed),
+            // Friday 2026-11-27, the day after Thanksgiving, closes at 13:00 (UTC-5)
+            (utc(2026, 11, 27, 17, 59), Session::Regular),
+            (utc(2026, 11, 27, 18, 0), Session::AfterHours),
+            (utc(2026, 11, 27, 22, 0), Session::Closed),
+            // Thanksgiving itself and a Saturday
+            (utc(2026, 11, 26, 16, 0), Session::Closed),
+            (utc(2026, 10, 17, 16, 0), Session::Closed),
+        ];
+        for (timestamp, session) in table {
+            assert_eq!(nyse.session_at(timestamp), session, "{}", timestamp);
+        }
+    }
+
+    #[test]
+    fn next_session_start_skips_weekends_holidays_and_follows_dst() {
+        let nyse = nyse();
+        let table = [
+            // Already open
+            (utc(2026, 10, 19, 14, 0), false, utc(2026, 10, 19, 14, 0)),
+            // Thanksgiving morning: the half day after it still opens at 09:30
+            (utc(2026, 11, 26, 15, 0), false, utc(2026, 11, 27, 14, 30)),
+            // Thursday July 2 2026 after the close: July 3 is a holiday, then the weekend
+            (utc(2026, 7, 2, 21, 0), false, utc(2026, 7, 6, 13, 30)),
+            // Friday before the switch to daylight saving time (March 8 2026)
+            (utc(2026, 3, 6, 22, 0), false, utc(2026, 3, 9, 13, 30)),
+            // Extended hours start at 04:00 on Monday
+            (utc(2026, 10, 17, 12, 0), true, utc(2026, 10, 19, 8, 0)),
+            // Pre-market counts as open when extended
+            (utc(2026, 10, 19, 9, 0), true, utc(2026, 10, 19, 9, 0)),
+        ];
+        for (timestamp, extended, expected) in table {
+            assert_eq!(nyse.next_session_start(timestamp, extended), expected, "{} extended {}", timestamp, extended);
+        }
+    }
+}
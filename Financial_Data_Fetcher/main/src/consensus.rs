@@ -0,0 +1,92 @@
+// Consensus pricing: combine quotes from several providers and catch bad ticks
+
+/// Median of a non-empty list of prices
+pub fn median(prices: &[f64]) -> f64 {
+    let mut sorted = prices.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Percentage distance of `price` from `reference`
+pub fn deviation_pct(price: f64, reference: f64) -> f64 {
+    ((price - reference) / reference).abs() * 100.0
+}
+
+/// Guards against single-tick spikes relative to the last accepted price.
+///
+/// A quote that jumps more than `max_jump_pct` is held back. If the next quote
+/// lands close to the held one the move was real and it is accepted; otherwise
+/// the held quote is dropped as a spike.
+#[derive(Debug, Default)]
+pub struct SpikeFilter {
+    pending: Option<f64>,  // Suspicious quote waiting for confirmation
+}
+
+impl SpikeFilter {
+    /// Returns Ok(price) if the quote can be stored, Err(reason) if it is rejected
+    pub fn check(&mut self, price: f64, last_price: Option<f64>, max_jump_pct: f64) -> Result<f64, String> {
+        let last = match last_price {
+            Some(last) if last > 0.0 => last,
+            _ => return Ok(price),  // Nothing to compare against yet
+        };
+
+        let jump = deviation_pct(price, last);
+        if jump <= max_jump_pct {
+            self.pending = None;
+            return Ok(price);
+        }
+
+        // A second quote near the held one confirms the move
+        if let Some(pending) = self.pending.take() {
+            if deviation_pct(price, pending) <= max_jump_pct {
+                return Ok(price);
+            }
+        }
+
+        self.pending = Some(price);
+        Err(format!("{:.2} is {:.1}% away from last price {:.2}, holding until confirmed", price, jump, last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(&[5.0]), 5.0);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+    }
+
+    #[test]
+    fn spike_is_held_then_confirmed() {
+        let mut filter = SpikeFilter::default();
+        assert!(filter.check(100.0, Some(50.0), 20.0).is_err());
+        assert_eq!(filter.check(101.0, Some(50.0), 20.0), Ok(101.0));
+    }
+
+    #[test]
+    fn unconfirmed_spike_is_dropped() {
+        let mut filter = SpikeFilter::default();
+        assert!(filter.check(100.0, Some(50.0), 20.0).is_err());
+        assert_eq!(filter.check(51.0, Some(50.0), 20.0), Ok(51.0));
+
+        // The dropped quote no longer confirms anything
+        assert!(filter.check(100.0, Some(51.0), 20.0).is_err());
+    }
+
+    #[test]
+    fn non_finite_quotes_are_never_accepted() {
+        let mut filter = SpikeFilter::default();
+        assert!(filter.check(f64::NAN, Some(50.0), 20.0).is_err());
+        assert!(filter.check(f64::NAN, Some(50.0), 20.0).is_err());
+        assert!(filter.check(f64::INFINITY, Some(50.0), 20.0).is_err());
+        assert_eq!(filter.check(50.5, Some(50.0), 20.0), Ok(50.5));
+    }
+}
@@ -0,0 +1,151 @@
+// API keys for authenticated provider tiers, read from the environment or a secrets file
+use std::collections::HashMap; // For keys by provider name
+use std::env;            // For key environment variables
+use std::error::Error;   // For error handling
+use std::fmt;            // For the redacting Debug impl
+use std::fs;             // For reading the secrets file
+use std::path::Path;     // For checking the secrets file
+use std::sync::OnceLock; // For the process-wide key table
+
+use crate::config::CredentialConfig;
+use crate::log::warning;
+
+/// Keys found at startup, by provider name
+static CREDENTIALS: OnceLock<HashMap<String, Credential>> = OnceLock::new();
+
+/// An API key; it is never printed, only attached to requests
+pub struct Secret(String);
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+/// Where a provider expects its key
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placement {
+    Header(String),  // e.g. "x-cg-demo-api-key"
+    Query(String),   // e.g. "apikey"
+}
+
+/// One provider's key and how to send it
+#[derive(Debug)]
+pub struct Credential {
+    key: Secret,
+    pub placement: Placement,
+    pub plan: Option<String>,  // CoinGecko tier: "demo" or "pro"
+}
+
+impl Credential {
+    /// Adds the key to an outgoing request
+    pub fn apply(&self, request: ureq::Request) -> ureq::Request {
+        match &self.placement {
+            Placement::Header(name) => request.set(name, &self.key.0),
+            Placement::Query(name) => request.query(name, &self.key.0),
+        }
+    }
+
+    /// `text` with the key blanked out (error messages can echo the request URL)
+    pub fn redact(&self, text: &str) -> String {
+        if self.key.0.is_empty() {
+            return text.to_string();
+        }
+        text.replace(&self.key.0, "***")
+    }
+}
+
+/// Looks up a key for every configured provider; call once at startup
+pub fn configure(
+    config: &HashMap<String, CredentialConfig>,
+    secrets_file: &str,
+) -> Result<(), Box<dyn Error>> {
+    let secrets = read_secrets(secrets_file)?;
+    let mut credentials = HashMap::new();
+
+    for (provider, settings) in config {
+        // The environment wins over the secrets file so a deployment can override it
+        let env_var = settings
+            .env
+            .clone()
+            .unwrap_or_else(|| format!("{}_API_KEY", provider.to_uppercase()));
+        let key = match env::var(&env_var).ok().filter(|key| !key.trim().is_empty()) {
+            Some(key) => key.trim().to_string(),
+            None => match secrets.get(provider) {
+                Some(key) => key.clone(),
+                None => {
+                    warning!("No API key for {} (set {} or add it to {}), using the public tier", provider, env_var, secrets_file);
+                    continue;
+                }
+            },
+        };
+
+        credentials.insert(
+            provider.clone(),
+            Credential {
+                key: Secret(key),
+                placement: placement(provider, settings)?,
+                plan: settings.plan.clone(),
+            },
+        );
+    }
+
+    CREDENTIALS
+        .set(credentials)
+        .map_err(|_| "credentials are already configured")?;
+    Ok(())
+}
+
+/// The key for `provider`, if one was configured and found
+pub fn for_provider(provider: &str) -> Option<&'static Credential> {
+    CREDENTIALS.get()?.get(provider)
+}
+
+/// Error text for a failed request, with the provider's key (if any) removed
+pub fn redact(text: &str, credential: Option<&Credential>) -> String {
+    match credential {
+        Some(credential) => credential.redact(text),
+        None => text.to_string(),
+    }
+}
+
+/// How `provider` receives its key; CoinGecko's headers follow from the plan
+fn placement(provider: &str, settings: &CredentialConfig) -> Result<Placement, Box<dyn Error>> {
+    if let Some(header) = &settings.header {
+        return Ok(Placement::Header(header.clone()));
+    }
+    if let Some(query) = &settings.query {
+        return Ok(Placement::Query(query.clone()));
+    }
+    match (provider, settings.plan.as_deref()) {
+        ("coingecko", Some("pro")) => Ok(Placement::Header("x-cg-pro-api-key".to_string())),
+        ("coingecko", _) => Ok(Placement::Header("x-cg-demo-api-key".to_string())),
+        _ => Err(format!("credentials for '{}' need a header or query name", provider).into()),
+    }
+}
+
+/// Reads `{"provider": "key", ...}` from the secrets file (missing file = no keys)
+fn read_secrets(path: &str) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    if !Path::new(path).exists() {
+        return Ok(HashMap::new());
+    }
+    warn_if_shared(path);
+    let contents = fs::read_to_string(path)?;
+    // Don't echo serde's message, it can quote part of the file
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("invalid {} at line {}", path, e.line()).into())
+}
+
+/// Keys shouldn't be readable by other users
+#[cfg(unix)]
+fn warn_if_shared(path: &str) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.permissions().mode() & 0o077 != 0 {
+            warning!("{} is readable by other users, consider chmod 600", path);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_shared(_path: &str) {}
@@ -0,0 +1,251 @@
+// Service mode: a pidfile so only one instance runs, and a health file the `status` command reads
+use serde::{Deserialize, Serialize}; // For the health file
+use std::error::Error;   // For error handling
+use std::fmt;            // For printing the status report
+use std::fs::{self, OpenOptions}; // For the pidfile and health file
+use std::io::{self, Write}; // For writing the pidfile
+use std::path::{Path, PathBuf}; // For file paths
+use std::process;        // For this process's id
+
+use crate::asset::{format_unix, unix_now, Pricing, SourceStatus};
+use crate::config::DaemonConfig;
+
+/// Holds the pidfile for as long as the service runs and removes it on the way out
+pub struct Pidfile {
+    path: PathBuf,
+}
+
+impl Pidfile {
+    /// Writes this process's id to `path`, refusing if another live instance holds it.
+    /// A pidfile left behind by a crash is taken over.
+    pub fn acquire(path: &str) -> Result<Pidfile, Box<dyn Error>> {
+        let path = PathBuf::from(path);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", process::id())?;
+                    return Ok(Pidfile { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match read_pid(&path) {
+                    Some(pid) if is_running(pid) => {
+                        return Err(format!("already running (pid {} in {})", pid, path.display()).into())
+                    }
+                    _ => fs::remove_file(&path)?,  // Stale, try once more
+                },
+                Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+            }
+        }
+        Err(format!("{}: could not take over the stale pidfile", path.display()).into())
+    }
+}
+
+impl Drop for Pidfile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Contents of the health file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub pid: u32,
+    pub started: u64,              // Unix time the service started
+    pub last_cycle: Option<u64>,   // Last cycle that fetched or streamed any price
+    pub last_success: Option<u64>, // Last cycle in which nothing failed
+    pub cycles: u64,               // Cycles so far
+    pub failures: usize,           // Assets that failed in the last cycle
+    pub assets: Vec<AssetHealth>,
+}
+
+/// Latest price of one asset as of the last cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetHealth {
+    pub name: String,
+    pub price: Option<f64>,
+    pub updated: Option<u64>,
+    pub source: Option<String>,
+    #[serde(default)]
+    pub open_circuits: Vec<String>, // Providers currently skipped by their circuit breaker
+}
+
+/// The service's health file, rewritten after every cycle
+pub struct HealthFile {
+    path: PathBuf,
+    health: Health,
+}
+
+impl HealthFile {
+    /// Starts a fresh health file for this process
+    pub fn create(path: &str) -> Result<HealthFile, Box<dyn Error>> {
+        let health_file = HealthFile {
+            path: PathBuf::from(path),
+            health: Health {
+                pid: process::id(),
+                started: unix_now(),
+                last_cycle: None,
+                last_success: None,
+                cycles: 0,
+                failures: 0,
+                assets: Vec::new(),
+            },
+        };
+        health_file.write()?;
+        Ok(health_file)
+    }
+
+    /// Records a cycle that handled `handled` prices, `failures` of which failed
+    pub fn record_cycle(
+        &mut self,
+        assets: &[Box<dyn Pricing>],
+        handled: usize,
+        failures: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = unix_now();
+        let health = &mut self.health;
+        health.cycles += 1;
+        health.last_cycle = Some(now);
+        health.failures = failures;
+        if handled > 0 && failures == 0 {
+            health.last_success = Some(now);
+        }
+        health.assets = assets
+            .iter()
+            .map(|asset| AssetHealth {
+                name: asset.name().to_string(),
+                price: asset.last_price(),
+                updated: asset.last_updated(),
+                source: asset.last_source().map(str::to_string),
+                open_circuits: asset
+                    .sources()
+                    .into_iter()
+                    .filter(|(_, status)| matches!(status, SourceStatus::Open(_)))
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
+            })
+            .collect();
+        self.write()
+    }
+
+    /// Replaces the file in one step so `status` never reads half of it
+    fn write(&self) -> Result<(), Box<dyn Error>> {
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_string_pretty(&self.health)?)?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// What `status` found out about the service
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub pid: Option<u32>,       // From the pidfile
+    pub running: bool,          // That process is alive
+    pub health: Option<Health>, // From the health file
+    pub stale_after_secs: u64,
+    pub now: u64,
+}
+
+impl Status {
+    /// Reads the pidfile and health file of the configured service
+    pub fn query(config: &DaemonConfig) -> Status {
+        let pid = read_pid(Path::new(&config.pidfile));
+        let health = fs::read_to_string(&config.health_file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok());
+        Status {
+            pid,
+            running: pid.is_some_and(is_running),
+            health,
+            stale_after_secs: config.stale_after_secs,
+            now: unix_now(),
+        }
+    }
+
+    /// Running, and a cycle succeeded recently (or it started recently)
+    pub fn healthy(&self) -> bool {
+        let Some(health) = self.health.as_ref().filter(|_| self.running) else {
+            return false;
+        };
+        let reference = health.last_success.unwrap_or(health.started);
+        self.now.saturating_sub(reference) <= self.stale_after_secs
+    }
+
+    /// Exit code for `status`, following the LSB convention: 0 healthy, 1 running but
+    /// unhealthy, 3 not running
+    pub fn exit_code(&self) -> i32 {
+        match (self.running, self.healthy()) {
+            (true, true) => 0,
+            (true, false) => 1,
+            (false, _) => 3,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.pid, self.running) {
+            (Some(pid), true) => writeln!(f, "running (pid {})", pid)?,
+            (Some(pid), false) => writeln!(f, "not running (stale pidfile for pid {})", pid)?,
+            (None, _) => writeln!(f, "not running")?,
+        }
+        let Some(health) = &self.health else {
+            return writeln!(f, "no health file");
+        };
+        let ago = |time: Option<u64>| match time {
+            Some(time) => format!("{} ({}s ago)", format_unix(time), self.now.saturating_sub(time)),
+            None => "never".to_string(),
+        };
+        writeln!(f, "started:      {}", ago(Some(health.started)))?;
+        writeln!(f, "last cycle:   {}, {} failed", ago(health.last_cycle), health.failures)?;
+        writeln!(f, "last success: {}", ago(health.last_success))?;
+        writeln!(f, "cycles:       {}", health.cycles)?;
+        for asset in &health.assets {
+            match asset.price {
+                Some(price) => writeln!(
+                    f,
+                    "  {:<12} {:.2} via {}, updated {}",
+                    asset.name,
+                    price,
+                    asset.source.as_deref().unwrap_or("-"),
+                    ago(asset.updated)
+                )?,
+                None => writeln!(f, "  {:<12} no price yet", asset.name)?,
+            }
+            if !asset.open_circuits.is_empty() {
+                writeln!(f, "  {:<12} circuit open: {}", "", asset.open_circuits.join(", "))?;
+            }
+        }
+        if self.running && !self.healthy() {
+            writeln!(f, "unhealthy: no successful cycle in the last {}s", self.stale_after_secs)?;
+        }
+        Ok(())
+    }
+}
+
+/// Process id stored in a pidfile (None if missing or unreadable)
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// True if a process with this id exists. /proc lists every process whoever owns it, so a
+/// service started as another user still counts as running.
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// True if a process with this id exists (`kill -0` sends no signal, it only checks). A process
+/// owned by another user fails with "not permitted" but is alive all the same.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(pid: u32) -> bool {
+    match process::Command::new("kill").args(["-0", &pid.to_string()]).output() {
+        Ok(output) => output.status.success() || String::from_utf8_lossy(&output.stderr).contains("not permitted"),
+        Err(_) => true,  // Can't tell, so don't take over or report it dead
+    }
+}
+
+/// Without `kill -0`, a pidfile is trusted as long as it exists
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
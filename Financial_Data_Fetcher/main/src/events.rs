@@ -0,0 +1,98 @@
+// In-process publish/subscribe of accepted prices, so storage and other consumers plug in
+use std::sync::mpsc::{self, Receiver, Sender}; // For subscribers on other threads
+
+use crate::config::Config;
+
+/// One accepted price, published after the spike filter and before display
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceUpdate {
+    pub asset: String,   // Asset name, e.g. "Bitcoin"
+    pub price: f64,      // USD
+    pub timestamp: u64,  // Unix time the price was fetched or streamed
+    pub source: String,  // Provider(s) that produced it, e.g. "coinbase" or "coingecko+kraken"
+}
+
+/// Anything that wants to hear about new prices
+pub trait Subscriber: Send {
+    /// Called for every update, in the order the subscribers registered
+    fn on_update(&mut self, update: &PriceUpdate);
+
+    /// Called on shutdown so buffering subscribers can write out
+    fn flush(&mut self) {}
+
+    /// Called about once a second, also while no prices arrive, for work done on a timer
+    /// (e.g. writing out buffered records)
+    fn tick(&mut self) {}
+
+    /// Called after the config file was reloaded, with the new config
+    fn reconfigure(&mut self, _config: &Config) {}
+}
+
+/// Plain closures can subscribe directly
+impl<F> Subscriber for F
+where
+    F: FnMut(&PriceUpdate) + Send,
+{
+    fn on_update(&mut self, update: &PriceUpdate) {
+        self(update)
+    }
+}
+
+/// Forwards updates to another thread; a dropped receiver is ignored
+impl Subscriber for Sender<PriceUpdate> {
+    fn on_update(&mut self, update: &PriceUpdate) {
+        let _ = self.send(update.clone());
+    }
+}
+
+/// The subscriber list the fetch loop publishes to
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<Box<dyn Subscriber>>,
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus::default()
+    }
+
+    /// Adds a subscriber; it sees every update published from now on
+    pub fn subscribe(&mut self, subscriber: impl Subscriber + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    /// Subscribes a channel and returns its receiving end, for consumers on other threads
+    pub fn channel(&mut self) -> Receiver<PriceUpdate> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribe(tx);
+        rx
+    }
+
+    /// Hands an update to every subscriber in registration order
+    pub fn publish(&mut self, update: &PriceUpdate) {
+        for subscriber in &mut self.subscribers {
+            subscriber.on_update(update);
+        }
+    }
+
+    /// Asks every subscriber to write out what it has buffered
+    pub fn flush(&mut self) {
+        for subscriber in &mut self.subscribers {
+            subscriber.flush();
+        }
+    }
+
+    /// Gives every subscriber its timer tick
+    pub fn tick(&mut self) {
+        for subscriber in &mut self.subscribers {
+            subscriber.tick();
+        }
+    }
+
+    /// Hands a reloaded config to every subscriber
+    pub fn reconfigure(&mut self, config: &Config) {
+        for subscriber in &mut self.subscribers {
+            subscriber.reconfigure(config);
+        }
+    }
+}
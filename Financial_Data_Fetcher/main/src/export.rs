@@ -0,0 +1,284 @@
+// Export of stored price history to Parquet and JSONL for notebooks and warehouses
+use std::error::Error;   // For error handling
+use std::fs::File;       // For the output file
+use std::io::{BufWriter, Write}; // For writing JSONL rows
+use std::path::{Path, PathBuf}; // For output paths
+use std::sync::Arc;      // Parquet shares the schema and properties
+
+use chrono::{DateTime, SecondsFormat};
+use parquet::basic::Compression;
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde::Serialize;
+
+use crate::config::{AssetConfig, Config};
+use crate::providers::QUOTE_CURRENCY;
+use crate::storage::PriceStore;
+
+/// Parquet schema of an export; JSONL rows carry the same fields
+const PARQUET_SCHEMA: &str = "
+message price_history {
+    REQUIRED INT64 timestamp (TIMESTAMP(MILLIS, true));
+    REQUIRED BYTE_ARRAY asset (UTF8);
+    REQUIRED BYTE_ARRAY currency (UTF8);
+    REQUIRED DOUBLE price;
+    REQUIRED BYTE_ARRAY source (UTF8);
+}
+";
+
+/// Rows per Parquet row group, keeps memory bounded for long histories
+const ROW_GROUP_SIZE: usize = 100_000;
+
+/// Output file formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Jsonl,    // One JSON object per line, timestamp as RFC 3339 UTC
+    Parquet,  // Typed columns, timestamp as UTC milliseconds, Snappy compressed
+}
+
+impl Format {
+    /// Looks up a format by its command line name ("jsonl" or "parquet")
+    pub fn by_name(name: &str) -> Result<Format, Box<dyn Error>> {
+        match name.to_lowercase().as_str() {
+            "jsonl" | "json" => Ok(Format::Jsonl),
+            "parquet" => Ok(Format::Parquet),
+            other => Err(format!("unknown export format '{}' (use jsonl or parquet)", other).into()),
+        }
+    }
+
+    /// File extension for default output names
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Jsonl => "jsonl",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+/// One exported price
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportRow {
+    pub timestamp: u64,   // Unix seconds
+    pub asset: String,    // Asset name from the config
+    pub currency: String, // Currency of `price` (always the quote currency, USD)
+    pub price: f64,
+    pub source: String,   // Provider(s) that produced the price
+}
+
+/// JSONL line layout
+#[derive(Serialize)]
+struct JsonRow<'a> {
+    timestamp: String,
+    asset: &'a str,
+    currency: &'a str,
+    price: f64,
+    source: &'a str,
+}
+
+/// Exports one asset (by name) or all of them, returning the row count and the file written
+pub fn export(
+    config: &Config,
+    format: Format,
+    asset: Option<&str>,
+    output: Option<&Path>,
+) -> Result<(usize, PathBuf), Box<dyn Error>> {
+    let assets = config.select_assets(asset)?;
+    let rows = load(config, &assets)?;
+    let path = output.map(Path::to_path_buf).unwrap_or_else(|| default_path(&assets, format));
+    write(&rows, format, &path)?;
+    Ok((rows.len(), path))
+}
+
+/// Reads the stored history of the given assets, each oldest first, in config order
+pub fn load(config: &Config, assets: &[&AssetConfig]) -> Result<Vec<ExportRow>, Box<dyn Error>> {
+    let mut rows = Vec::new();
+    for asset in assets {
+        let store = PriceStore::new(&asset.file, &config.storage);
+        let history = store.history().map_err(|e| format!("{}: {}", asset.name, e))?;
+        rows.extend(history.into_iter().map(|record| ExportRow {
+            timestamp: record.timestamp,
+            asset: asset.name.clone(),
+            currency: QUOTE_CURRENCY.to_string(),
+            price: record.price,
+            source: record.source,
+        }));
+    }
+    Ok(rows)
+}
+
+/// Default output path: "bitcoin_prices.parquet" for one asset, "price_history.parquet" for all
+pub fn default_path(assets: &[&AssetConfig], format: Format) -> PathBuf {
+    match assets {
+        [asset] => Path::new(&asset.file).with_extension(format.extension()),
+        _ => PathBuf::from(format!("price_history.{}", format.extension())),
+    }
+}
+
+/// Writes the rows to `path` in the given format, replacing any existing file
+pub fn write(rows: &[ExportRow], format: Format, path: &Path) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Jsonl => write_jsonl(rows, path),
+        Format::Parquet => write_parquet(rows, path),
+    }
+}
+
+fn write_jsonl(rows: &[ExportRow], path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for row in rows {
+        let line = JsonRow {
+            timestamp: format_rfc3339(row.timestamp),
+            asset: &row.asset,
+            currency: &row.currency,
+            price: row.price,
+            source: &row.source,
+        };
+        serde_json::to_writer(&mut out, &line)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_parquet(rows: &[ExportRow], path: &Path) -> Result<(), Box<dyn Error>> {
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, properties)?;
+
+    for chunk in rows.chunks(ROW_GROUP_SIZE) {
+        let mut row_group = writer.next_row_group()?;
+        // Columns come back in schema order
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match (index, column.untyped()) {
+                (0, ColumnWriter::Int64ColumnWriter(values)) => {
+                    let millis: Vec<i64> = chunk.iter().map(|row| row.timestamp as i64 * 1000).collect();
+                    values.write_batch(&millis, None, None)?;
+                }
+                (1, ColumnWriter::ByteArrayColumnWriter(values)) => {
+                    values.write_batch(&strings(chunk, |row| &row.asset), None, None)?;
+                }
+                (2, ColumnWriter::ByteArrayColumnWriter(values)) => {
+                    values.write_batch(&strings(chunk, |row| &row.currency), None, None)?;
+                }
+                (3, ColumnWriter::DoubleColumnWriter(values)) => {
+                    let prices: Vec<f64> = chunk.iter().map(|row| row.price).collect();
+                    values.write_batch(&prices, None, None)?;
+                }
+                (4, ColumnWriter::ByteArrayColumnWriter(values)) => {
+                    values.write_batch(&strings(chunk, |row| &row.source), None, None)?;
+                }
+                _ => return Err(format!("unexpected column {} in export schema", index).into()),
+            }
+            column.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+/// One string field of every row as Parquet byte arrays
+fn strings(rows: &[ExportRow], field: impl Fn(&ExportRow) -> &String) -> Vec<ByteArray> {
+    rows.iter().map(|row| ByteArray::from(field(row).as_str())).collect()
+}
+
+/// "2026-10-18T14:05:00Z" for a unix timestamp
+fn format_rfc3339(timestamp: u64) -> String {
+    match DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(time) => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        None => timestamp.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::PriceRecord;
+    use parquet::basic::Type;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+    use std::{env, fs, process};
+
+    /// Bitcoin (two prices) and Ethereum (one) stored in a fresh directory
+    fn stored(test: &str) -> (Config, PathBuf) {
+        let dir = env::temp_dir().join(format!("fdf-export-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut config = Config::default();
+        config.assets.truncate(2);
+        config.storage.fsync = false;
+        let prices: [&[(u64, f64)]; 2] = [&[(1_790_812_800, 84000.5), (1_790_812_860, 84010.0)], &[(1_790_812_830, 3100.25)]];
+        for (asset, prices) in config.assets.iter_mut().zip(prices) {
+            asset.file = dir.join(&asset.file).to_string_lossy().into_owned();
+            let mut store = PriceStore::new(&asset.file, &config.storage);
+            for (timestamp, price) in prices {
+                let record = PriceRecord { timestamp: *timestamp, price: *price, source: "test".to_string(), session: None };
+                store.append(*timestamp, &record.to_line()).unwrap();
+            }
+            store.flush().unwrap();
+        }
+        (config, dir)
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let (config, dir) = stored("jsonl");
+        let path = dir.join("out.jsonl");
+        assert_eq!(export(&config, Format::Jsonl, None, Some(&path)).unwrap(), (3, path.clone()));
+
+        let rows: Vec<serde_json::Value> =
+            fs::read_to_string(&path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            serde_json::json!({
+                "timestamp": "2026-10-01T00:00:00Z",
+                "asset": "Bitcoin",
+                "currency": "USD",
+                "price": 84000.5,
+                "source": "test",
+            })
+        );
+        assert_eq!(rows[2]["asset"], "Ethereum");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parquet_round_trip() {
+        let (config, dir) = stored("parquet");
+        let path = dir.join("out.parquet");
+        assert_eq!(export(&config, Format::Parquet, None, Some(&path)).unwrap(), (3, path.clone()));
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 3);
+        let columns: Vec<(String, Type)> = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|column| (column.name().to_string(), column.physical_type()))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("timestamp".to_string(), Type::INT64),
+                ("asset".to_string(), Type::BYTE_ARRAY),
+                ("currency".to_string(), Type::BYTE_ARRAY),
+                ("price".to_string(), Type::DOUBLE),
+                ("source".to_string(), Type::BYTE_ARRAY),
+            ]
+        );
+
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(|row| row.unwrap()).collect();
+        assert_eq!(rows[1].get_timestamp_millis(0).unwrap(), 1_790_812_860_000);
+        assert_eq!(rows[1].get_string(1).unwrap(), "Bitcoin");
+        assert_eq!(rows[1].get_double(3).unwrap(), 84010.0);
+        assert_eq!(rows[2].get_string(1).unwrap(), "Ethereum");
+        assert_eq!(rows[2].get_string(2).unwrap(), "USD");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+// Fetch scheduling shared by the console loop and the terminal UI
+use std::error::Error;   // For error handling
+use std::time::Duration; // For time intervals
+
+use crate::asset::{unix_now, Pricing};
+use crate::config::Config;
+use crate::events::{EventBus, PriceUpdate, Subscriber};
+use crate::shutdown;
+
+/// Small delay between assets to avoid rate limiting
+pub const ASSET_DELAY: Duration = Duration::from_secs(2);
+
+/// How often streamed prices are picked up while waiting for the next poll
+pub const STREAM_POLL: Duration = Duration::from_secs(1);
+
+/// How often a waiting scheduler gives subscribers their tick
+pub const TICK: Duration = Duration::from_secs(1);
+
+/// Tracks when each asset is next due, following its own interval and market hours
+pub struct Scheduler {
+    next_due: Vec<u64>,  // Unix time per asset, same order as the asset list
+    streaming: bool,     // Some asset has a feed, so wake up every STREAM_POLL
+    asset_delay: Duration, // Pause between fetches in one pass
+    bus: EventBus,       // Where accepted prices are published
+}
+
+impl Scheduler {
+    /// Every asset starts out due, so the first pass fetches everything once
+    pub fn new(asset_count: usize) -> Scheduler {
+        Scheduler {
+            next_due: vec![0; asset_count],
+            streaming: false,
+            asset_delay: ASSET_DELAY,
+            bus: EventBus::new(),
+        }
+    }
+
+    /// Registers a consumer of every accepted price, e.g. storage or alerts
+    pub fn subscribe(&mut self, subscriber: impl Subscriber + 'static) {
+        self.bus.subscribe(subscriber);
+    }
+
+    /// The event bus, for subscribing channels or publishing from outside the loop
+    pub fn bus(&mut self) -> &mut EventBus {
+        &mut self.bus
+    }
+
+    /// Asks every subscriber to write out what it buffered, used on the way out
+    pub fn flush(&mut self) {
+        self.bus.flush();
+    }
+
+    /// Changes the pause between fetches in one pass (ASSET_DELAY by default); simulated assets
+    /// have no rate limits to respect
+    pub fn set_asset_delay(&mut self, delay: Duration) {
+        self.asset_delay = delay;
+    }
+
+    /// Passes a reloaded config on to the subscribers
+    pub fn reconfigure(&mut self, config: &Config) {
+        self.bus.reconfigure(config);
+    }
+
+    /// Follows a change to the asset list: `order` has one entry per new asset, the index it had
+    /// before or None for a new one (which is due straight away)
+    pub fn rearrange(&mut self, order: &[Option<usize>]) {
+        self.next_due = order
+            .iter()
+            .map(|old| old.and_then(|index| self.next_due.get(index).copied()).unwrap_or(0))
+            .collect();
+    }
+
+    /// Unix time the asset at `index` is next due
+    pub fn due(&self, index: usize) -> u64 {
+        self.next_due.get(index).copied().unwrap_or(0)
+    }
+
+    /// Moves the asset at `index` to be due at `time`
+    pub fn set_due(&mut self, index: usize, time: u64) {
+        if let Some(next_due) = self.next_due.get_mut(index) {
+            *next_due = time;
+        }
+    }
+
+    /// Publishes any streamed prices and fetches every asset that is due, calling `on_fetch`
+    /// after each. Returns how many assets were polled; stops early if a shutdown is requested.
+    pub fn run_due(
+        &mut self,
+        assets: &mut [Box<dyn Pricing>],
+        mut on_fetch: impl FnMut(&dyn Pricing, &Result<f64, Box<dyn Error>>),
+    ) -> usize {
+        self.streaming = assets.iter().any(|asset| asset.has_stream());
+        let mut fetched = 0;
+        for (asset, next_due) in assets.iter_mut().zip(self.next_due.iter_mut()) {
+            // Streamed prices take the same path as polled ones
+            if let Some(result) = asset.take_streamed() {
+                publish(&mut self.bus, asset.as_ref(), &result);
+                on_fetch(asset.as_ref(), &result);
+            }
+
+            if unix_now() < *next_due {
+                continue;
+            }
+            // A live feed makes the poll redundant, the providers stay as a fallback
+            if asset.stream_is_live(unix_now()) {
+                *next_due = asset.next_poll(unix_now());
+                continue;
+            }
+            // Small delay between assets to avoid rate limiting
+            if fetched > 0 && !shutdown::sleep(self.asset_delay) {
+                break;
+            }
+
+            let result = asset.fetch_price();
+            publish(&mut self.bus, asset.as_ref(), &result);
+            on_fetch(asset.as_ref(), &result);
+
+            *next_due = asset.next_poll(unix_now());
+            fetched += 1;
+        }
+        fetched
+    }
+
+    /// Seconds until the next asset is due
+    pub fn seconds_until_next(&self) -> u64 {
+        let next = self.next_due.iter().copied().min().unwrap_or(0);
+        next.saturating_sub(unix_now())
+    }
+
+    /// Sleeps until the next asset is due, or at most STREAM_POLL while feeds are running,
+    /// ticking the subscribers and calling `watch` every TICK on the way. A reload request
+    /// (SIGHUP, or one `watch` makes) also ends the wait. Returns false if interrupted by shutdown.
+    pub fn wait(&mut self, mut watch: impl FnMut()) -> bool {
+        let mut remaining = Duration::from_secs(self.seconds_until_next());
+        if self.streaming {
+            remaining = remaining.min(STREAM_POLL);
+        }
+        loop {
+            let slice = remaining.min(TICK);
+            if !shutdown::sleep_or_reload(slice) {
+                return false;
+            }
+            self.bus.tick();
+            watch();
+            remaining = remaining.saturating_sub(slice);
+            if remaining.is_zero() || shutdown::reload_pending() {
+                return true;
+            }
+        }
+    }
+}
+
+/// Publishes a successful price so subscribers see it before the display does
+fn publish(bus: &mut EventBus, asset: &dyn Pricing, result: &Result<f64, Box<dyn Error>>) {
+    if let Ok(price) = result {
+        bus.publish(&PriceUpdate {
+            asset: asset.name().to_string(),
+            price: *price,
+            timestamp: asset.last_updated().unwrap_or_else(unix_now),
+            source: asset.last_source().unwrap_or("unknown").to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn wait_sleeps_again_once_a_reload_is_taken() {
+        let mut scheduler = Scheduler::new(1);
+        scheduler.set_due(0, unix_now() + 3);
+
+        // A pending reload ends the wait straight away
+        shutdown::request_reload();
+        let start = Instant::now();
+        assert!(scheduler.wait(|| {}));
+        assert!(start.elapsed() < Duration::from_millis(500), "waited {:?}", start.elapsed());
+
+        // Once it's taken, the wait lasts until the asset is due again
+        assert!(shutdown::take_reload());
+        let start = Instant::now();
+        assert!(scheduler.wait(|| {}));
+        assert!(start.elapsed() >= Duration::from_secs(1), "waited only {:?}", start.elapsed());
+    }
+}
@@ -0,0 +1,87 @@
+// Provider parsers checked against recorded API responses in fixtures/
+use std::fs;             // For reading fixture files
+use std::path::PathBuf;  // For building fixture paths
+
+use crate::config::{ProviderConfig, StreamConfig};
+use crate::providers::{self, Provider};
+use crate::stream::{self, StreamSource};
+use crate::validate;
+
+/// (provider, symbol, fixture file, expected price)
+const CASES: &[(&str, &str, &str, f64)] = &[
+    ("coingecko", "bitcoin", "coingecko_bitcoin.json", 76617.0),
+    ("yahoo", "^GSPC", "yahoo_gspc.json", 5363.36),
+    ("coinbase", "BTC", "coinbase_btc.json", 84612.345),
+    ("kraken", "BTC", "kraken_btc.json", 84620.0),
+    ("binance", "BTC", "binance_btc.json", 84618.01),
+];
+
+/// (streaming provider, symbol, fixture file with one ticker message, expected price)
+const STREAM_CASES: &[(&str, &str, &str, f64)] = &[
+    ("coinbase", "BTC", "coinbase_ws_ticker.json", 84611.97),
+    ("binance", "BTC", "binance_ws_ticker.json", 84618.01),
+];
+
+/// (provider, symbol, fixture file with a changed or bad response, text the error must contain)
+const DRIFT_CASES: &[(&str, &str, &str, &str)] = &[
+    ("yahoo", "^GSPC", "yahoo_gspc_drifted.json", "at `chart.result[0].meta`: missing field `regularMarketPrice`"),
+    ("coingecko", "bitcoin", "coingecko_bitcoin_mistyped.json", "at `bitcoin.usd`: invalid type: string"),
+    ("coinbase", "BTC", "coinbase_btc_negative.json", "non-positive price"),
+];
+
+/// Reads a recorded response from the crate's fixtures/ directory
+fn fixture(file: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(file);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn provider(name: &str, symbol: &str) -> Box<dyn Provider> {
+    let config = ProviderConfig {
+        provider: name.to_string(),
+        symbol: symbol.to_string(),
+    };
+    providers::build(&config).unwrap_or_else(|e| panic!("{}: {}", name, e))
+}
+
+fn stream_source(name: &str, symbol: &str) -> Box<dyn StreamSource> {
+    let config = StreamConfig {
+        provider: name.to_string(),
+        symbol: symbol.to_string(),
+        url: None,
+    };
+    stream::build(&config).unwrap_or_else(|e| panic!("{}: {}", name, e))
+}
+
+#[test]
+fn provider_responses_parse() {
+    for (name, symbol, file, expected) in CASES {
+        let price = provider(name, symbol)
+            .parse(&fixture(file))
+            .unwrap_or_else(|e| panic!("{} {}: {}", name, file, e));
+        assert_eq!(price, *expected, "{} {}", name, file);
+    }
+}
+
+#[test]
+fn ticker_messages_parse() {
+    for (name, symbol, file, expected) in STREAM_CASES {
+        let price = stream_source(name, symbol)
+            .parse(&fixture(file))
+            .unwrap_or_else(|e| panic!("{}-ws {}: {}", name, file, e));
+        assert_eq!(price, Some(*expected), "{}-ws {}", name, file);
+    }
+}
+
+/// Changed or bad responses must be rejected with the field named
+#[test]
+fn drifted_responses_are_rejected() {
+    for (name, symbol, file, expected) in DRIFT_CASES {
+        let result = provider(name, symbol)
+            .parse(&fixture(file))
+            .and_then(|price| validate::check_price(name, price));
+        match result {
+            Err(e) => assert!(e.to_string().contains(expected), "{} {}: got \"{}\" expected \"{}\"", name, file, e, expected),
+            Ok(price) => panic!("{} {}: accepted {} expected an error", name, file, price),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+// Currency conversion: USD quotes into the configured display currency
+use serde::Deserialize;  // For JSON deserialization
+use std::collections::HashMap; // For the rates table
+use std::error::Error;   // For error handling
+use std::time::{Duration, Instant}; // For cache expiry
+
+use crate::config::DisplayConfig;
+use crate::http;
+use crate::log::warning;
+
+/// Converts USD amounts into one display currency, caching the exchange rate
+pub struct Fx {
+    currency: String,        // ISO code, e.g. "EUR"
+    locale: Locale,          // Number formatting rules
+    ttl: Duration,           // How long a fetched rate is trusted
+    rate: Option<(f64, Instant)>, // Cached USD -> currency rate and when it was fetched
+    failed: Option<Instant>, // Last failed fetch, not retried until the TTL has passed
+}
+
+/// Currencies the Frankfurter API has ECB reference rates for (plus USD itself)
+pub const CURRENCIES: &[&str] = &[
+    "AUD", "BGN", "BRL", "CAD", "CHF", "CNY", "CZK", "DKK", "EUR", "GBP", "HKD", "HUF", "IDR", "ILS", "INR", "ISK",
+    "JPY", "KRW", "MXN", "MYR", "NOK", "NZD", "PHP", "PLN", "RON", "SEK", "SGD", "THB", "TRY", "USD", "ZAR",
+];
+
+/// Checks the display settings without building a converter, used when the config is loaded
+pub fn validate(config: &DisplayConfig) -> Result<(), Box<dyn Error>> {
+    let currency = config.currency.to_uppercase();
+    if !CURRENCIES.contains(&currency.as_str()) {
+        return Err(format!("unsupported display currency '{}'", config.currency).into());
+    }
+    if let Some(tag) = &config.locale {
+        Locale::parse(tag)?;
+    }
+    Ok(())
+}
+
+impl Fx {
+    /// Builds a converter from the display settings
+    pub fn new(config: &DisplayConfig) -> Result<Fx, Box<dyn Error>> {
+        let currency = config.currency.to_uppercase();
+        let locale = match &config.locale {
+            Some(tag) => Locale::parse(tag)?,
+            None => Locale::default_for(&currency),
+        };
+        Ok(Fx {
+            currency,
+            locale,
+            ttl: Duration::from_secs(config.fx_ttl_secs),
+            rate: None,
+            failed: None,
+        })
+    }
+
+    /// Formats a USD amount in the display currency.
+    ///
+    /// If no rate can be fetched the amount is shown in USD rather than guessed.
+    pub fn format_usd(&mut self, usd: f64) -> String {
+        match self.rate() {
+            Some(rate) => format_money(usd * rate, &self.currency, self.locale),
+            None => format_money(usd, "USD", Locale::default_for("USD")),
+        }
+    }
+
+    /// USD -> display currency rate, refreshed once the cached one is older than the TTL.
+    /// A failed refresh is remembered for the TTL too, so a dead API costs one timeout per TTL
+    /// rather than one per formatted price.
+    fn rate(&mut self) -> Option<f64> {
+        if self.currency == "USD" {
+            return Some(1.0);
+        }
+        let cached = self.rate.map(|(rate, _)| rate);
+        let recent = |time: Option<Instant>| time.is_some_and(|time| time.elapsed() < self.ttl);
+        if recent(self.rate.map(|(_, fetched)| fetched)) || recent(self.failed) {
+            return cached;
+        }
+        match fetch_rate(&self.currency) {
+            Ok(rate) => {
+                self.rate = Some((rate, Instant::now()));
+                self.failed = None;
+                Some(rate)
+            }
+            Err(e) => {
+                self.failed = Some(Instant::now());
+                match cached {
+                    // A stale rate beats no rate at all
+                    Some(_) => warning!("FX refresh failed ({}), using cached {} rate", e, self.currency),
+                    None => warning!(
+                        "FX rate for {} unavailable ({}), showing USD for the next {}s",
+                        self.currency,
+                        e,
+                        self.ttl.as_secs()
+                    ),
+                }
+                cached
+            }
+        }
+    }
+}
+
+/// Fetches the USD -> `currency` rate from the Frankfurter (ECB reference rates) API
+fn fetch_rate(currency: &str) -> Result<f64, Box<dyn Error>> {
+    // {"amount": 1.0, "base": "USD", "date": "2026-10-16", "rates": {"EUR": 0.8571}}
+    #[derive(Deserialize)]
+    struct RatesResponse {
+        rates: HashMap<String, f64>,
+    }
+
+    let url = format!("https://api.frankfurter.app/latest?from=USD&to={}", currency);
+    let mut response = http::agent().get(&url).call()?.into_json::<RatesResponse>()?;
+    match response.rates.remove(currency) {
+        Some(rate) if rate > 0.0 => Ok(rate),
+        _ => Err(format!("no USD/{} rate in response", currency).into()),
+    }
+}
+
+/// Number formatting conventions for a locale
+#[derive(Debug, Clone, Copy)]
+pub struct Locale {
+    thousands: char,      // Grouping separator
+    decimal: char,        // Decimal separator
+    symbol_after: bool,   // "1.234,56 €" instead of "€1,234.56"
+}
+
+impl Locale {
+    const ENGLISH: Locale = Locale { thousands: ',', decimal: '.', symbol_after: false };
+    const GERMAN: Locale = Locale { thousands: '.', decimal: ',', symbol_after: true };
+    const FRENCH: Locale = Locale { thousands: '\u{202f}', decimal: ',', symbol_after: true };
+    const SWISS: Locale = Locale { thousands: '\'', decimal: '.', symbol_after: false };
+
+    /// Parses a locale tag like "en-GB" or "de-DE" (only the language part matters)
+    fn parse(tag: &str) -> Result<Locale, Box<dyn Error>> {
+        let language = tag.split(['-', '_']).next().unwrap_or("").to_lowercase();
+        match language.as_str() {
+            "en" | "ja" => Ok(Locale::ENGLISH),
+            "de" | "nl" | "it" | "es" => Ok(Locale::GERMAN),
+            "fr" => Ok(Locale::FRENCH),
+            _ => Err(format!("unsupported locale '{}'", tag).into()),
+        }
+    }
+
+    /// Sensible default when only the currency is configured
+    fn default_for(currency: &str) -> Locale {
+        match currency {
+            "EUR" => Locale::GERMAN,
+            "CHF" => Locale::SWISS,
+            _ => Locale::ENGLISH,
+        }
+    }
+}
+
+/// Currency symbol and number of minor digits
+fn currency_info(currency: &str) -> (&str, usize) {
+    match currency {
+        "USD" => ("$", 2),
+        "EUR" => ("€", 2),
+        "GBP" => ("£", 2),
+        "JPY" => ("¥", 0),
+        "CHF" => ("CHF ", 2),
+        other => (other, 2),  // Unknown currencies fall back to their ISO code
+    }
+}
+
+/// Formats an amount with grouping, decimals and symbol placement for the locale
+pub fn format_money(amount: f64, currency: &str, locale: Locale) -> String {
+    let (symbol, digits) = currency_info(currency);
+    let fixed = format!("{:.*}", digits, amount.abs());
+    let (whole, fraction) = match fixed.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (fixed.as_str(), None),
+    };
+
+    // Insert the grouping separator every three digits from the right
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(locale.thousands);
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push(locale.decimal);
+        grouped.push_str(fraction);
+    }
+
+    let sign = if amount < 0.0 { "-" } else { "" };
+    if locale.symbol_after {
+        format!("{}{} {}", sign, grouped, symbol.trim())
+    } else {
+        format!("{}{}{}", sign, symbol, grouped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur(ttl_secs: u64) -> Fx {
+        Fx::new(&DisplayConfig { currency: "EUR".to_string(), locale: None, fx_ttl_secs: ttl_secs }).unwrap()
+    }
+
+    #[test]
+    fn money_follows_the_locale() {
+        let table = [
+            (1234567.891, "USD", Locale::ENGLISH, "$1,234,567.89"),
+            (-1234.5, "EUR", Locale::GERMAN, "-1.234,50 €"),
+            (999.0, "GBP", Locale::ENGLISH, "£999.00"),
+            (1234.5, "EUR", Locale::FRENCH, "1\u{202f}234,50 €"),
+            (1234.5, "CHF", Locale::SWISS, "CHF 1'234.50"),
+            (1234567.4, "JPY", Locale::ENGLISH, "¥1,234,567"),
+            (12.0, "SEK", Locale::ENGLISH, "SEK12.00"),
+        ];
+        for (amount, currency, locale, expected) in table {
+            assert_eq!(format_money(amount, currency, locale), expected);
+        }
+    }
+
+    #[test]
+    fn locales_and_currencies_are_validated() {
+        let config = |currency: &str, locale: Option<&str>| DisplayConfig {
+            currency: currency.to_string(),
+            locale: locale.map(str::to_string),
+            fx_ttl_secs: 60,
+        };
+        assert!(validate(&config("eur", Some("de-DE"))).is_ok());
+        assert!(validate(&config("XYZ", None)).is_err());
+        assert!(validate(&config("EUR", Some("tlh"))).is_err());
+    }
+
+    #[test]
+    fn failed_fetch_is_not_retried_within_the_ttl() {
+        // No rate and a recent failure: amounts stay in USD without another request
+        let mut fx = eur(3600);
+        fx.failed = Some(Instant::now());
+        assert_eq!(fx.rate(), None);
+        assert_eq!(fx.format_usd(1500.0), "$1,500.00");
+
+        // An expired rate is still used while the failure is recent
+        let stale = Instant::now().checked_sub(Duration::from_secs(7200)).unwrap();
+        fx.rate = Some((0.5, stale));
+        assert_eq!(fx.format_usd(1500.0), "750,00 €");
+    }
+
+    #[test]
+    fn usd_needs_no_rate() {
+        let mut fx = Fx::new(&DisplayConfig::default()).unwrap();
+        assert_eq!(fx.rate(), Some(1.0));
+        assert_eq!(fx.format_usd(0.5), "$0.50");
+    }
+}
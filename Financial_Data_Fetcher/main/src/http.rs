@@ -0,0 +1,83 @@
+// Shared HTTP agent: one configured client reused by every provider and the FX lookup
+use std::error::Error;   // For error handling
+use std::fs;             // For reading the CA bundle
+use std::sync::{Arc, OnceLock}; // For the process-wide agent
+use std::time::Duration; // For timeouts
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use ureq::{Agent, AgentBuilder, Proxy};
+
+use crate::config::HttpConfig;
+use crate::credentials::Credential;
+
+/// The agent built from the config (connections are pooled inside it)
+static AGENT: OnceLock<Agent> = OnceLock::new();
+
+/// Builds the shared agent; call once at startup before any request is made
+pub fn configure(config: &HttpConfig) -> Result<(), Box<dyn Error>> {
+    let agent = build(config)?;
+    AGENT.set(agent).map_err(|_| "HTTP agent is already configured")?;
+    Ok(())
+}
+
+/// The shared agent, with default settings if `configure` was never called
+pub fn agent() -> &'static Agent {
+    AGENT.get_or_init(|| build(&HttpConfig::default()).unwrap_or_else(|_| Agent::new()))
+}
+
+/// GET through the shared agent, attaching `credential` if the provider has one and
+/// making the request conditional on `etag`
+pub fn get(url: &str, credential: Option<&Credential>, etag: Option<&str>) -> Result<ureq::Response, Box<ureq::Error>> {
+    let mut request = agent().get(url);
+    if let Some(credential) = credential {
+        request = credential.apply(request);
+    }
+    if let Some(etag) = etag {
+        request = request.set("If-None-Match", etag);
+    }
+    request.call().map_err(Box::new)
+}
+
+/// Creates an agent from the settings, validating the proxy URL and CA bundle
+fn build(config: &HttpConfig) -> Result<Agent, Box<dyn Error>> {
+    let mut builder = AgentBuilder::new()
+        .user_agent(&config.user_agent)
+        .timeout_connect(Duration::from_secs(config.connect_timeout_secs))
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .max_idle_connections(config.max_idle_connections)
+        .max_idle_connections_per_host(config.max_idle_connections.min(4));
+
+    builder = match &config.proxy {
+        Some(url) => builder.proxy(Proxy::new(url).map_err(|e| format!("invalid proxy: {}", e))?),
+        None => builder.try_proxy_from_env(true),
+    };
+
+    if let Some(path) = &config.ca_bundle {
+        builder = builder.tls_config(tls_config(path)?);
+    }
+    Ok(builder.build())
+}
+
+/// TLS settings trusting the bundled Mozilla roots plus every certificate in `path`
+fn tls_config(path: &str) -> Result<Arc<rustls::ClientConfig>, Box<dyn Error>> {
+    let mut roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let pem = fs::read(path).map_err(|e| format!("ca_bundle {}: {}", path, e))?;
+    let mut added = 0;
+    for cert in CertificateDer::pem_slice_iter(&pem) {
+        let cert = cert.map_err(|e| format!("ca_bundle {}: {}", path, e))?;
+        roots.add(cert).map_err(|e| format!("ca_bundle {}: {}", path, e))?;
+        added += 1;
+    }
+    if added == 0 {
+        return Err(format!("ca_bundle {}: no certificates found", path).into());
+    }
+
+    let config = rustls::ClientConfig::builder_with_provider(rustls::crypto::ring::default_provider().into())
+        .with_protocol_versions(&[&rustls::version::TLS12, &rustls::version::TLS13])?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
@@ -0,0 +1,212 @@
+// Import of the old bare-number price files (bitcoin_prices.txt etc.) into structured storage
+use std::error::Error;   // For error handling
+use std::fmt;            // For printing the import report
+use std::fs;             // For reading the legacy file and its mtime
+use std::path::{Path, PathBuf}; // For legacy file paths
+use std::time::UNIX_EPOCH; // For the mtime as unix time
+
+use crate::asset::{format_unix, unix_now};
+use crate::calendar::MarketCalendar;
+use crate::config::{AssetConfig, Config, RetentionAction};
+use crate::storage::{PriceRecord, PriceStore};
+
+/// Source recorded for imported prices; also how a repeated import is detected
+pub const LEGACY_SOURCE: &str = "legacy";
+
+/// Seconds in a day, the unit of the retention policy
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// A line that didn't parse: its 1-based number and text
+pub type Rejected = (usize, String);
+
+/// What an import did for one asset
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    pub asset: String,
+    pub path: PathBuf,                  // Legacy file read
+    pub imported: usize,                // Records written
+    pub span: Option<(u64, u64)>,       // Inferred timestamps of the first and last record
+    pub files: Vec<PathBuf>,            // Price files written
+    pub rejected: Vec<Rejected>,        // Every line that didn't parse
+    pub expiring: Option<String>,       // What retention will do to the older imported records
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: imported {} records from {}", self.asset, self.imported, self.path.display())?;
+        if let Some((first, last)) = self.span {
+            write!(f, " ({} to {}, approximate)", format_unix(first), format_unix(last))?;
+        }
+        for file in &self.files {
+            write!(f, "\n  wrote {}", file.display())?;
+        }
+        if let Some(expiring) = &self.expiring {
+            write!(f, "\n  note: {}", expiring)?;
+        }
+        for (line, text) in &self.rejected {
+            write!(f, "\n  line {}: could not parse {:?}", line, text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the old program wrote an asset's prices: "bitcoin_prices.txt" next to "bitcoin_prices.csv"
+pub fn legacy_path(asset: &AssetConfig) -> PathBuf {
+    Path::new(&asset.file).with_extension("txt")
+}
+
+/// Parses a legacy file's contents into timestamped prices.
+///
+/// The old loop appended one line per poll, so the last line is dated `end` (the file's mtime)
+/// and each earlier line one `interval` before the next. Unparseable lines still took a poll
+/// slot and are returned with their 1-based line numbers. Blank lines are ignored.
+pub fn parse(text: &str, end: u64, interval: u64) -> (Vec<(u64, f64)>, Vec<Rejected>) {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+
+    let mut prices = Vec::new();
+    let mut rejected = Vec::new();
+    for (slot, (number, line)) in lines.iter().enumerate() {
+        let steps_back = (lines.len() - 1 - slot) as u64;
+        match line.parse::<f64>() {
+            Ok(price) if price.is_finite() && price > 0.0 => {
+                prices.push((end.saturating_sub(steps_back * interval), price));
+            }
+            _ => rejected.push((*number, line.to_string())),
+        }
+    }
+    (prices, rejected)
+}
+
+/// Imports one asset's legacy file (its default path unless `path` is given) into its price files,
+/// dating the lines `interval` seconds apart (the asset's `interval_secs` if None).
+///
+/// Records go straight into rotated files for their dates. The live file isn't rotated and
+/// retention doesn't run, so nothing imported is removed on the way in.
+pub fn import(
+    config: &Config,
+    asset: &AssetConfig,
+    path: Option<&Path>,
+    interval: Option<u64>,
+) -> Result<ImportReport, Box<dyn Error>> {
+    let path = path.map(Path::to_path_buf).unwrap_or_else(|| legacy_path(asset));
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let modified = fs::metadata(&path)?.modified()?;
+    let end = modified.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+
+    let store = PriceStore::new(&asset.file, &config.storage);
+    if store.history()?.iter().any(|record| record.source == LEGACY_SOURCE) {
+        return Err(format!("{}: legacy prices were already imported", asset.name).into());
+    }
+
+    // Scheduled assets carry the session tag like any other record
+    let calendar = match &asset.schedule {
+        Some(name) => Some(MarketCalendar::by_name(name)?),
+        None => None,
+    };
+
+    let (prices, rejected) = parse(&text, end, interval.unwrap_or(asset.interval_secs));
+    let records: Vec<PriceRecord> = prices
+        .iter()
+        .map(|(timestamp, price)| PriceRecord {
+            timestamp: *timestamp,
+            price: *price,
+            source: LEGACY_SOURCE.to_string(),
+            session: calendar.as_ref().map(|calendar| calendar.session_at(*timestamp).tag().to_string()),
+        })
+        .collect();
+    let files = store.write_archive(&records)?;
+
+    // Retention still applies from the next rotation on, say so rather than surprise anyone
+    let expiring = config.storage.retention.as_ref().and_then(|retention| {
+        let cutoff = unix_now().saturating_sub(retention.days * DAY_SECS);
+        let old = records.iter().filter(|record| record.timestamp < cutoff).count();
+        let fate = match retention.action {
+            RetentionAction::Delete => "deleted".to_string(),
+            RetentionAction::Downsample => format!("downsampled to one per {}s", retention.downsample_secs),
+        };
+        (old > 0).then(|| {
+            format!("{} records are older than the {}-day retention and will be {} at the next rotation", old, retention.days, fate)
+        })
+    });
+
+    Ok(ImportReport {
+        asset: asset.name.clone(),
+        path,
+        imported: prices.len(),
+        span: prices.first().zip(prices.last()).map(|(first, last)| (first.0, last.0)),
+        files,
+        rejected,
+        expiring,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Rotation;
+    use std::time::{Duration, SystemTime};
+    use std::{env, process};
+
+    /// 2026-10-01 00:00:00 UTC
+    const DAY_ONE: u64 = 1_790_812_800;
+
+    #[test]
+    fn lines_are_dated_back_from_the_end() {
+        let text = "100\n\n101.5\nabc\n-3\n102\n";
+        let (prices, rejected) = parse(text, 10_000, 60);
+
+        // Blank lines don't count, bad ones still took a poll
+        assert_eq!(prices, [(9_760, 100.0), (9_820, 101.5), (10_000, 102.0)]);
+        assert_eq!(rejected, [(4, "abc".to_string()), (5, "-3".to_string())]);
+    }
+
+    #[test]
+    fn import_writes_dated_files_and_refuses_a_second_run() {
+        let dir = env::temp_dir().join(format!("fdf-legacy-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut config = Config::default();
+        config.storage.rotate = Rotation::Daily;
+        config.storage.fsync = false;
+        let mut asset = config.assets[0].clone();
+        asset.file = dir.join("btc.csv").to_string_lossy().into_owned();
+        asset.interval_secs = 3600;
+
+        // 30 hourly lines ending at noon on October 2nd
+        let legacy = legacy_path(&asset);
+        let lines: Vec<String> = (0..30).map(|hour| format!("{}", 1000 + hour)).collect();
+        fs::write(&legacy, lines.join("\n")).unwrap();
+        let end = DAY_ONE + 36 * 3600;
+        fs::File::options()
+            .write(true)
+            .open(&legacy)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(end))
+            .unwrap();
+
+        let report = import(&config, &asset, None, None).unwrap();
+        assert_eq!(report.imported, 30);
+        assert_eq!(report.span, Some((end - 29 * 3600, end)));
+        assert_eq!(report.files, [dir.join("btc-2026-10-01.csv"), dir.join("btc-2026-10-02.csv")]);
+        assert!(!dir.join("btc.csv").exists(), "the live file was written");
+
+        // Hours 7-23 of October 1st, then 0-12 of the 2nd
+        let first = fs::read_to_string(dir.join("btc-2026-10-01.csv")).unwrap();
+        assert_eq!(first.lines().count(), 17);
+        assert_eq!(first.lines().next(), Some(format!("{},1000,legacy", DAY_ONE + 7 * 3600).as_str()));
+        let store = PriceStore::new(&asset.file, &config.storage);
+        assert_eq!(store.history().unwrap().len(), 30);
+        assert_eq!(store.last_record().unwrap().map(|record| record.timestamp), Some(end));
+
+        // A repeated import is refused, whatever interval it would use
+        let error = import(&config, &asset, None, Some(16)).unwrap_err();
+        assert!(error.to_string().contains("already imported"), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
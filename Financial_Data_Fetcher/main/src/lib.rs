@@ -0,0 +1,99 @@
+//! Price fetching for crypto and equity indices: providers with failover and consensus, rotated CSV
+//! storage, market-hours scheduling and portfolio valuation.
+//!
+//! The `main` binary is a thin console/dashboard front end over this crate. To embed fetching in
+//! another program, load a [`Config`], call [`init`] once, and drive the assets with a [`Scheduler`].
+//! Accepted prices are published as [`PriceUpdate`] events; storage ([`Recorder`]) is just one
+//! subscriber, closures and channels can be added alongside it:
+//!
+//! ```no_run
+//! use financial_data_fetcher::{build_assets, init, Config, PriceUpdate, Recorder, Scheduler};
+//!
+//! let config = Config::load("config.json")?;
+//! init(&config)?;
+//! let mut assets = build_assets(&config)?;
+//! let mut scheduler = Scheduler::new(assets.len());
+//! scheduler.subscribe(Recorder::from_config(&config)?);
+//! scheduler.subscribe(|update: &PriceUpdate| {
+//!     if update.price > 100_000.0 {
+//!         println!("{} crossed 100k via {}", update.asset, update.source);
+//!     }
+//! });
+//! scheduler.run_due(&mut assets, |asset, result| {
+//!     println!("{}: {:?}", asset.name(), result.as_ref().ok());
+//! });
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::error::Error;   // For error handling
+
+pub mod asset;     // Pricing trait and the Asset struct
+pub mod breaker;   // Per-provider circuit breakers
+pub mod cache;     // Short-lived cache of provider responses
+pub mod calendar;  // Exchange trading hours and holidays
+pub mod config;    // config.json loading and defaults
+pub mod consensus; // Median pricing and spike rejection
+pub mod credentials; // API keys from the environment or a secrets file
+pub mod daemon;    // Pidfile, health file and status of the service mode
+pub mod events;    // Price update publish/subscribe
+pub mod export;    // Parquet and JSONL export of stored history
+pub mod fetcher;   // Per-asset fetch scheduling
+pub mod fx;        // Exchange rates and currency formatting
+pub mod legacy;    // Import of the old bare-number price files
+pub mod http;      // Shared HTTP agent (proxy, user agent, TLS)
+pub mod log;       // Warning output (stderr or log file)
+pub mod portfolio; // Holdings valuation and P&L
+pub mod providers; // CoinGecko, Yahoo and other price sources
+pub mod recorder;  // Storage subscriber writing the price files
+pub mod reload;    // Config file watching and hot reload
+pub mod shutdown;  // Signal handling: flush on Ctrl-C/SIGTERM, reload on SIGHUP
+pub mod simulate;  // Offline replay and random walk through the normal loop
+pub mod storage;   // Price files, rotation and retention
+pub mod stream;    // Websocket ticker feeds and a local stand-in
+pub mod validate;  // Response schema checks, price sanity checks and quarantine
+
+#[cfg(test)]
+mod fixtures;      // Parser tests against recorded responses
+
+/// The README's Rust snippets, compiled by `cargo test` so the documented usage can't drift
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
+
+pub use asset::{Asset, Pricing, SourceStatus};
+pub use calendar::{MarketCalendar, Session};
+pub use config::Config;
+pub use events::{EventBus, PriceUpdate, Subscriber};
+pub use fetcher::Scheduler;
+pub use fx::Fx;
+pub use portfolio::Portfolio;
+pub use providers::Provider;
+pub use recorder::Recorder;
+pub use storage::{PriceRecord, PriceStore};
+
+/// Applies the process-wide settings (HTTP agent, response cache, API keys); call once before fetching
+pub fn init(config: &Config) -> Result<(), Box<dyn Error>> {
+    // Every request goes through one agent so proxy, user agent and TLS settings apply everywhere
+    http::configure(&config.http).map_err(|e| format!("http: {}", e))?;
+
+    // Responses are shared between callers for a few seconds
+    cache::configure(&config.cache);
+
+    // Bodies that fail to parse are kept for inspection
+    validate::configure(&config.quarantine_dir);
+
+    // API keys are looked up once; they're never printed
+    credentials::configure(&config.credentials, &config.secrets_file)
+        .map_err(|e| format!("credentials: {}", e))?;
+    Ok(())
+}
+
+/// Builds every configured asset, resuming each from its newest saved record
+pub fn build_assets(config: &Config) -> Result<Vec<Box<dyn Pricing>>, Box<dyn Error>> {
+    let mut assets: Vec<Box<dyn Pricing>> = Vec::new();
+    for asset_config in &config.assets {
+        let asset = Asset::from_config(asset_config, &config.storage, &config.breaker)
+            .map_err(|e| format!("{}: {}", asset_config.name, e))?;
+        assets.push(Box::new(asset));
+    }
+    Ok(assets)
+}
@@ -0,0 +1,36 @@
+// Warning output: stderr normally, a log file while the full-screen UI owns the terminal
+use std::fs::{File, OpenOptions}; // For the optional log file
+use std::io::{self, Write}; // For writing messages
+use std::sync::Mutex;    // Shared between the fetch thread and the UI
+
+use crate::asset::unix_now;
+
+/// Where warnings go (None = stderr)
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// Sends all further warnings to `path` instead of stderr
+pub fn to_file(path: &str) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    *LOG_FILE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(file);
+    Ok(())
+}
+
+/// Writes one warning line
+pub fn write(message: &str) {
+    let mut log_file = LOG_FILE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match log_file.as_mut() {
+        Some(file) => {
+            let _ = writeln!(file, "{} {}", unix_now(), message);
+        }
+        None => eprintln!("{}", message),
+    }
+}
+
+/// `eprintln!`-style macro that respects the log destination
+#[macro_export]
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::log::write(&format!($($arg)*))
+    };
+}
+pub use warning;
@@ -1,227 +1,378 @@
 // Import necessary libraries
-use serde::Deserialize;  // For JSON deserialization
-use std::error::Error;   // For error handling
-use std::fs::OpenOptions; // For file operations
-use std::io::Write;      // For writing to files
-use std::thread;         // For sleep functionality
-use std::time::Duration; // For time intervals
-use ureq;               // For HTTP requests
-
-// 1. STRUCT DEFINITIONS
-// Each struct represents a financial asset and stores its last fetched price
-
-/// Bitcoin asset with price tracking
-#[derive(Debug)]  // Enable debug printing
-struct Bitcoin {
-    last_price: Option<f64>,  // Stores the most recent price (None if not fetched yet)
-}
+use std::env;            // For command line arguments
+use std::path::PathBuf;  // For export and import paths
+use std::process;        // For exiting on bad configuration
+use std::time::Duration; // For the simulation's pacing
 
-/// Ethereum asset with price tracking
-#[derive(Debug)]
-struct Ethereum {
-    last_price: Option<f64>,
-}
+mod tui;       // Full-screen dashboard
 
-/// S&P 500 index with price tracking
-#[derive(Debug)]
-struct SP500 {
-    last_price: Option<f64>,
-}
+use financial_data_fetcher::config::CONFIG_PATH;
+use financial_data_fetcher::{asset, build_assets, cache, calendar, daemon, export, init, legacy, reload, shutdown, simulate, stream};
+use financial_data_fetcher::{Config, Fx, Portfolio, Pricing, Recorder, Scheduler, SourceStatus};
+use financial_data_fetcher::warning;
 
-// 2. PRICING TRAIT AND IMPLEMENTATIONS
-/// Defines common behavior for all priceable assets
-trait Pricing {
-    /// Fetches the current price from API
-    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>>;
-    
-    /// Saves the price to a file
-    fn save_to_file(&self, price: f64) -> Result<(), Box<dyn Error>>;
-    
-    /// Formats the price for display
-    fn display(&self) -> String;
-}
+/// Main entry point of the application
+fn main() {
+    // Optional subcommand: market, ws-standin, export, import, status, daemon,
+    // simulate or tui (default is the console loop)
+    let command = env::args().nth(1);
 
-// Bitcoin implementation
-impl Pricing for Bitcoin {
-    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>> {
-        // Nested structs for JSON parsing
-        #[derive(Deserialize)]
-        struct ApiResponse {
-            bitcoin: BitcoinPrice,  // Matches CoinGecko's response structure
-        }
-        #[derive(Deserialize)]
-        struct BitcoinPrice {
-            usd: f64,  // Price in USD
-        }
-
-        let url = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd";
-        
-        // Make HTTP request with rate limit handling
-        let response = match ureq::get(url).call() {
-            Ok(resp) => resp,
-            // If rate limited (429), wait 60 seconds and retry
-            Err(ureq::Error::Status(code, _)) if code == 429 => {
-                println!("Rate limited, waiting 60 seconds...");
-                thread::sleep(Duration::from_secs(60));
-                ureq::get(url).call()?
-            }
-            Err(e) => return Err(Box::new(e)),  // Propagate other errors
+    // `market [nyse|nasdaq] [unix-time]` prints the session, next open and holidays, then exits
+    if command.as_deref() == Some("market") {
+        let name = env::args().nth(2).unwrap_or_else(|| "nyse".to_string());
+        let timestamp = match env::args().nth(3).map(|arg| arg.parse::<u64>()) {
+            Some(Ok(timestamp)) => timestamp,
+            Some(Err(e)) => {
+                eprintln!("Error parsing unix time: {}", e);
+                process::exit(1);
+            }
+            None => asset::unix_now(),
         };
+        match calendar::MarketCalendar::by_name(&name) {
+            Ok(calendar) => print!("{}", calendar.report(timestamp)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-        // Parse JSON response and extract price
-        let response = response.into_json::<ApiResponse>()?;
-        let price = response.bitcoin.usd;
-        self.last_price = Some(price);  // Update last known price
-        Ok(price)
+    // `ws-standin [addr] [price]` serves fake exchange ticker feeds for trying out streaming offline
+    if command.as_deref() == Some("ws-standin") {
+        let addr = env::args().nth(2).unwrap_or_else(|| stream::STANDIN_ADDR.to_string());
+        let price = env::args().nth(3).and_then(|arg| arg.parse().ok()).unwrap_or(84000.0);
+        if let Err(e) = stream::serve_standin(&addr, price) {
+            eprintln!("Error running stand-in feed: {}", e);
+            process::exit(1);
+        }
+        return;
     }
 
-    fn save_to_file(&self, price: f64) -> Result<(), Box<dyn Error>> {
-        // Open file in append mode, create if doesn't exist
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("bitcoin_prices.txt")?;
-            
-        // Write price to file
-        writeln!(file, "{}", price)?;
-        Ok(())
+    // Ctrl-C / SIGTERM end the loop cleanly instead of killing it mid-write
+    if let Err(e) = shutdown::install_handler() {
+        eprintln!("Error installing shutdown handler: {}", e);
+        process::exit(1);
     }
 
-    fn display(&self) -> String {
-        // Format price with 2 decimal places, default to 0.0 if None
-        format!("Bitcoin: ${:.2}", self.last_price.unwrap_or(0.0))
+    // Load the asset list (built-in defaults if config.json is missing)
+    let mut config = match Config::load(CONFIG_PATH) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            process::exit(1);
+        }
+    };
+
+    // `status` reports on the running service from its pidfile and health file
+    if command.as_deref() == Some("status") {
+        let status = daemon::Status::query(&config.daemon);
+        print!("{}", status);
+        process::exit(status.exit_code());
     }
-}
 
-// Ethereum implementation (similar structure to Bitcoin)
-impl Pricing for Ethereum {
-    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>> {
-        #[derive(Deserialize)]
-        struct ApiResponse {
-            ethereum: EthereumPrice,
-        }
-        #[derive(Deserialize)]
-        struct EthereumPrice {
-            usd: f64,
-        }
-
-        let url = "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd";
-        
-        // Same rate limit handling as Bitcoin
-        let response = match ureq::get(url).call() {
-            Ok(resp) => resp,
-            Err(ureq::Error::Status(code, _)) if code == 429 => {
-                println!("Rate limited, waiting 60 seconds...");
-                thread::sleep(Duration::from_secs(60));
-                ureq::get(url).call()?
-            }
-            Err(e) => return Err(Box::new(e)),
-        };
+    // `export <jsonl|parquet> [asset|all] [output]` converts stored history and exits
+    if command.as_deref() == Some("export") {
+        let result = export::Format::by_name(&env::args().nth(2).unwrap_or_default()).and_then(|format| {
+            let asset = env::args().nth(3);
+            let output = env::args().nth(4).map(PathBuf::from);
+            export::export(&config, format, asset.as_deref(), output.as_deref())
+        });
+        match result {
+            Ok((rows, path)) => println!("Exported {} records to {}", rows, path.display()),
+            Err(e) => {
+                eprintln!("Error exporting: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-        let response = response.into_json::<ApiResponse>()?;
-        let price = response.ethereum.usd;
-        self.last_price = Some(price);
-        Ok(price)
+    // `import [asset|all] [legacy-file] [--interval secs]` ingests the old bare-number .txt files and exits
+    if command.as_deref() == Some("import") {
+        let mut args: Vec<String> = env::args().skip(2).collect();
+        let mut interval = None;  // Each asset's interval_secs
+        if let Some(flag) = args.iter().position(|arg| arg == "--interval") {
+            interval = match args.get(flag + 1).map(|secs| secs.parse::<u64>()) {
+                Some(Ok(secs)) if secs > 0 => Some(secs),
+                _ => {
+                    eprintln!("Error importing: --interval needs a number of seconds above 0");
+                    process::exit(1);
+                }
+            };
+            args.drain(flag..flag + 2);
+        }
+        let mut args = args.into_iter();
+        let name = args.next();
+        let file = args.next().map(PathBuf::from);
+        let assets = match config.select_assets(name.as_deref()) {
+            Ok(assets) if file.is_some() && assets.len() > 1 => {
+                eprintln!("Error importing: name one asset when giving a legacy file");
+                process::exit(1);
+            }
+            Ok(assets) => assets,
+            Err(e) => {
+                eprintln!("Error importing: {}", e);
+                process::exit(1);
+            }
+        };
+        let mut failed = false;
+        for asset in assets {
+            // With every asset selected, ones that never had a legacy file are just skipped
+            if file.is_none() && !legacy::legacy_path(asset).exists() {
+                println!("{}: no {}, skipped", asset.name, legacy::legacy_path(asset).display());
+                continue;
+            }
+            match legacy::import(&config, asset, file.as_deref(), interval) {
+                Ok(report) => println!("{}", report),
+                Err(e) => {
+                    eprintln!("Error importing: {}", e);
+                    failed = true;
+                }
+            }
+        }
+        process::exit(if failed { 1 } else { 0 });
     }
 
-    fn save_to_file(&self, price: f64) -> Result<(), Box<dyn Error>> {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("ethereum_prices.txt")?;
-        writeln!(file, "{}", price)?;
-        Ok(())
+    // `daemon` is the console loop as a service: one instance per pidfile, health file after each cycle.
+    // From here on errors leave through `exit`, which removes the pidfile (process::exit skips Drop).
+    let service = command.as_deref() == Some("daemon");
+    let pidfile = if service {
+        match daemon::Pidfile::acquire(&config.daemon.pidfile) {
+            Ok(pidfile) => Some(pidfile),
+            Err(e) => {
+                eprintln!("Error starting service: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // `simulate <replay|walk> [speed]` runs the console loop on stored history or a random walk
+    // instead of the providers, storing into the simulation directory
+    let simulation = if command.as_deref() == Some("simulate") {
+        let mode = simulate::Mode::by_name(&env::args().nth(2).unwrap_or_default()).and_then(|mode| {
+            if let Some(speed) = env::args().nth(3) {
+                config.simulation.speed = speed
+                    .parse()
+                    .ok()
+                    .filter(|speed: &f64| speed.is_finite() && *speed >= 0.0)
+                    .ok_or_else(|| format!("invalid speed '{}'", speed))?;
+            }
+            Ok(mode)
+        });
+        match mode {
+            Ok(mode) => Some(mode),
+            Err(e) => {
+                eprintln!("Error starting simulation: {}", e);
+                exit(pidfile, 1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Proxy/TLS settings, response cache and API keys apply to every provider
+    if let Err(e) = init(&config) {
+        eprintln!("Error configuring {}", e);
+        exit(pidfile, 1);
     }
 
-    fn display(&self) -> String {
-        format!("Ethereum: ${:.2}", self.last_price.unwrap_or(0.0))
+    // Prices are fetched in USD and converted for display
+    let mut fx = match Fx::new(&config.display) {
+        Ok(fx) => fx,
+        Err(e) => {
+            eprintln!("Error configuring display currency: {}", e);
+            exit(pidfile, 1);
+        }
+    };
+
+    // Initialize every asset, resuming from its saved history
+    let built = match simulation {
+        Some(mode) => simulate::build_assets(&config, mode).map(|assets| {
+            assets
+                .into_iter()
+                .map(|asset| {
+                    println!("{}: {}", asset.name(), asset.plan());
+                    Box::new(asset) as Box<dyn Pricing>
+                })
+                .collect()
+        }),
+        None => build_assets(&config),
+    };
+    let mut assets = match built {
+        Ok(assets) => assets,
+        Err(e) => {
+            eprintln!("Error configuring {}", e);
+            exit(pidfile, 1);
+        }
+    };
+
+    // Real assets pick up where the last run left off
+    for asset in &assets {
+        if let (Some(price), Some(updated)) = (asset.last_price(), asset.last_updated()) {
+            println!(
+                "{}: resuming from {:.2} saved at {} (via {})",
+                asset.name(),
+                price,
+                asset::format_unix(updated),
+                asset.last_source().unwrap_or("-")
+            );
+        }
     }
-}
 
-// S&P 500 implementation (uses Yahoo Finance API)
-impl Pricing for SP500 {
-    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>> {
-        // Yahoo Finance API endpoint for S&P 500 (^GSPC)
-        let url = "https://query1.finance.yahoo.com/v8/finance/chart/%5EGSPC";
-        
-        // Complex nested structures to match Yahoo's JSON response
-        #[derive(Deserialize)]
-        struct YahooResponse {
-            chart: Chart,
-        }
-        
-        #[derive(Deserialize)]
-        struct Chart {
-            result: Vec<ChartResult>,  // Array of results (we take first)
-        }
-        
-        #[derive(Deserialize)]
-        struct ChartResult {
-            meta: Meta,  // Metadata containing price
-        }
-        
-        #[derive(Deserialize)]
-        struct Meta {
-            #[serde(rename = "regularMarketPrice")]  // Map JSON field to Rust naming
-            regular_market_price: f64,
-        }
-
-        // Make request and parse JSON
-        let response = ureq::get(url)
-            .call()?
-            .into_json::<YahooResponse>()?;
-            
-        // Extract price from nested structure
-        let price = response.chart.result[0].meta.regular_market_price;
-        self.last_price = Some(price);
-        Ok(price)
+    // Simulated prices are stored, but never next to the real ones
+    if simulation.is_some() {
+        match simulate::redirect(&mut config) {
+            Ok(dir) => println!("Storing simulated prices in {}", dir.display()),
+            Err(e) => {
+                eprintln!("Error preparing simulation: {}", e);
+                exit(pidfile, 1);
+            }
+        }
     }
 
-    fn save_to_file(&self, price: f64) -> Result<(), Box<dyn Error>> {
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("sp500_prices.txt")?;
-        writeln!(file, "{}", price)?;
-        Ok(())
+    // Accepted prices are published to subscribers; storage is the first of them
+    let mut scheduler = Scheduler::new(assets.len());
+    if simulation.is_some() {
+        scheduler.set_asset_delay(Duration::ZERO);
     }
+    match Recorder::from_config(&config) {
+        Ok(recorder) => scheduler.subscribe(recorder),
+        Err(e) => {
+            eprintln!("Error opening price files: {}", e);
+            exit(pidfile, 1);
+        }
+    }
+
+    // Holdings are optional, only loaded when configured
+    let mut portfolio = match &config.portfolio {
+        Some(portfolio_config) => match Portfolio::load(portfolio_config, &config.assets) {
+            Ok(portfolio) => Some(portfolio),
+            Err(e) => {
+                eprintln!("Error loading portfolio: {}", e);
+                exit(pidfile, 1);
+            }
+        },
+        None => None,
+    };
 
-    fn display(&self) -> String {
-        format!("S&P 500: ${:.2}", self.last_price.unwrap_or(0.0))
+    // `tui` shows a live dashboard instead of scrolling output
+    if command.as_deref() == Some("tui") {
+        if let Err(e) = tui::run(assets, scheduler, fx, portfolio) {
+            eprintln!("Terminal error: {}", e);
+            exit(pidfile, 1);
+        }
+        return;
     }
-}
 
-/// Main entry point of the application
-fn main() {
-    // Initialize our three assets with no known price yet
-    let mut assets: Vec<Box<dyn Pricing>> = vec![
-        Box::new(Bitcoin { last_price: None }),
-        Box::new(Ethereum { last_price: None }),
-        Box::new(SP500 { last_price: None }),
-    ];
-
-    // Main program loop - runs indefinitely
-    loop {
-        println!("\nFetching new prices...");
-        
-        // Process each asset in sequence
-        for asset in &mut assets {
-            match asset.fetch_price() {
-                Ok(price) => {
-                    // Display and save successful fetches
-                    println!("{}", asset.display());
-                    if let Err(e) = asset.save_to_file(price) {
-                        eprintln!("Error saving price: {}", e);
+    let mut health = if service {
+        match daemon::HealthFile::create(&config.daemon.health_file) {
+            Ok(health) => Some(health),
+            Err(e) => {
+                eprintln!("Error writing health file: {}", e);
+                exit(pidfile, 1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Edits to config.json (or SIGHUP) end the wait and are applied before the next cycle,
+    // except to a simulation
+    let mut watcher = simulation.is_none().then(|| reload::ConfigWatcher::new(CONFIG_PATH));
+
+    // Main program loop - runs until Ctrl-C (or SIGTERM)
+    while !shutdown::requested() {
+        // Without a watcher nothing else clears a SIGHUP, and a pending one would end every wait
+        if watcher.is_none() {
+            shutdown::take_reload();
+        }
+        if watcher.as_mut().is_some_and(|watcher| watcher.changed()) {
+            match reload::load(CONFIG_PATH)
+                .and_then(|new| reload::apply(&mut config, new, &mut assets, &mut scheduler))
+            {
+                Ok(summary) => {
+                    println!("Reloaded {}: {}", CONFIG_PATH, summary);
+                    if !summary.unapplied.is_empty() {
+                        warning!("{}: {} changed, restart to apply", CONFIG_PATH, summary.unapplied.join(", "));
                     }
                 }
-                Err(e) => eprintln!("Error fetching price: {}", e),
+                Err(e) => eprintln!("Error reloading {}, keeping the running config: {}", CONFIG_PATH, e),
             }
-            // Small delay between assets to avoid rate limiting
-            thread::sleep(Duration::from_secs(2));
         }
 
-        // Wait 10 seconds before next full cycle
-        println!("Waiting 10 seconds before next fetch...");
-        thread::sleep(Duration::from_secs(10));
+        // With streaming feeds the loop also wakes every second, only announce real polls
+        let polling = scheduler.seconds_until_next() == 0;
+        if polling {
+            println!("\nFetching new prices...");
+        }
+
+        // Process each due asset in sequence, displaying successful fetches
+        let (mut handled, mut failures) = (0, 0);
+        let fetched = scheduler.run_due(&mut assets, |asset, result| {
+            handled += 1;
+            match result {
+                Ok(_) => println!("{}", asset.display(&mut fx)),
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("Error fetching price: {}", e);
+                }
+            }
+        });
+
+        // The service reports every cycle that did something
+        if let Some(health) = &mut health {
+            if handled > 0 {
+                if let Err(e) = health.record_cycle(&assets, handled, failures) {
+                    eprintln!("Error writing health file: {}", e);
+                }
+            }
+        }
+
+        // Value holdings with this round's prices
+        if let Some(portfolio) = &mut portfolio {
+            if fetched > 0 {
+                match portfolio.update(&assets, &mut fx) {
+                    Ok(report) => println!("{}", report),
+                    Err(e) => eprintln!("Error updating portfolio: {}", e),
+                }
+            }
+        }
+
+        // A replay (or a walk with a step limit) ends on its own
+        if simulation.is_some() && assets.iter().all(|asset| asset.finished()) {
+            println!("Simulation finished");
+            break;
+        }
+
+        // Sleep until the next asset is due
+        if polling {
+            println!("Response cache: {}", cache::stats());
+            for asset in &assets {
+                for (name, status) in asset.sources() {
+                    if let SourceStatus::Open(until) = status {
+                        println!("{}: {} circuit open until {}", asset.name(), name, asset::format_unix(until));
+                    }
+                }
+            }
+            println!("Waiting {} seconds before next fetch...", scheduler.seconds_until_next());
+        }
+        scheduler.wait(|| {
+            if let Some(watcher) = &mut watcher {
+                watcher.poll();
+            }
+        });
     }
-}
\ No newline at end of file
+
+    println!("Shutting down, flushing price files...");
+    scheduler.flush();
+}
+
+/// Ends the process with `code`, removing the service's pidfile first since process::exit
+/// doesn't run destructors
+fn exit(pidfile: Option<daemon::Pidfile>, code: i32) -> ! {
+    drop(pidfile);
+    process::exit(code)
+}
@@ -0,0 +1,308 @@
+// Portfolio tracking: value holdings with the latest fetched prices
+use std::collections::HashMap; // For looking up prices by asset name
+use std::error::Error;   // For error handling
+use std::fs::{self, OpenOptions}; // For reading holdings and history
+use std::io::Write;      // For writing to files
+use std::path::Path;     // For checking the history file
+
+use crate::asset::{unix_now, Pricing};
+use crate::config::{AssetConfig, PortfolioConfig};
+use crate::fx::Fx;
+
+/// Seconds in a day, the window for "day change"
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// One position from the holdings file
+#[derive(Debug)]
+pub struct Holding {
+    pub asset: String,     // Configured asset name, e.g. "Bitcoin"
+    pub quantity: f64,     // Units held
+    pub cost_basis: f64,   // Total USD paid for the position
+}
+
+/// Valuation of one position at the current prices
+pub struct Position<'a> {
+    pub holding: &'a Holding,
+    pub value: f64,  // quantity * last_price
+}
+
+/// Holdings plus the recorded portfolio-value time series
+pub struct Portfolio {
+    holdings: Vec<Holding>,
+    history_file: String,
+    history: Vec<(u64, f64)>,  // (unix timestamp, total value) from the last day, plus the
+                               // newest older one that day change compares against
+}
+
+impl Portfolio {
+    /// Reads the holdings file and any previously recorded values. Holdings name configured
+    /// assets (in any case); an unknown name is an error.
+    pub fn load(config: &PortfolioConfig, assets: &[AssetConfig]) -> Result<Portfolio, Box<dyn Error>> {
+        let holdings = parse_holdings(&fs::read_to_string(&config.holdings)?, assets)
+            .map_err(|e| format!("{}: {}", config.holdings, e))?;
+
+        // Bring back the value series so day change survives restarts
+        let mut history = Vec::new();
+        if Path::new(&config.history).exists() {
+            for line in fs::read_to_string(&config.history)?.lines() {
+                let mut fields = line.split(',');
+                if let (Some(timestamp), Some(value)) = (fields.next(), fields.next()) {
+                    if let (Ok(timestamp), Ok(value)) = (timestamp.parse(), value.parse()) {
+                        history.push((timestamp, value));
+                    }
+                }
+            }
+        }
+
+        let mut portfolio = Portfolio {
+            holdings,
+            history_file: config.history.clone(),
+            history,
+        };
+        portfolio.prune(unix_now());
+        Ok(portfolio)
+    }
+
+    /// Values every holding that has a price, appends the total to the history file
+    /// and returns a printable report
+    pub fn update(&mut self, assets: &[Box<dyn Pricing>], fx: &mut Fx) -> Result<String, Box<dyn Error>> {
+        let prices: HashMap<&str, f64> = assets
+            .iter()
+            .filter_map(|asset| asset.last_price().map(|price| (asset.name(), price)))
+            .collect();
+
+        let mut positions = Vec::new();
+        let mut missing = Vec::new();
+        for holding in &self.holdings {
+            match prices.get(holding.asset.as_str()) {
+                Some(price) => positions.push(Position { holding, value: holding.quantity * price }),
+                None => missing.push(holding.asset.as_str()),
+            }
+        }
+        let total: f64 = positions.iter().map(|p| p.value).sum();
+        let cost: f64 = positions.iter().map(|p| p.holding.cost_basis).sum();
+
+        // Build the report before recording, so day change compares against older values
+        let mut report = String::from("Portfolio:\n");
+        for position in &positions {
+            let pnl = position.value - position.holding.cost_basis;
+            let allocation = if total > 0.0 { position.value / total * 100.0 } else { 0.0 };
+            report.push_str(&format!(
+                "  {:<10} {:>12} {:>5.1}%  P&L {} ({})\n",
+                position.holding.asset,
+                fx.format_usd(position.value),
+                allocation,
+                signed(fx, pnl),
+                percent(pnl, position.holding.cost_basis),
+            ));
+        }
+        if !missing.is_empty() {
+            report.push_str(&format!("  no price yet for {}\n", missing.join(", ")));
+        }
+        report.push_str(&format!(
+            "  Total      {:>12}         P&L {} ({})\n",
+            fx.format_usd(total),
+            signed(fx, total - cost),
+            percent(total - cost, cost),
+        ));
+
+        let now = unix_now();
+        // Less than a day of history has nothing to compare against
+        match self.value_at_or_before(now.saturating_sub(DAY_SECS)) {
+            Some(previous) => report.push_str(&format!(
+                "  Day change {} ({})",
+                signed(fx, total - previous),
+                percent(total - previous, previous),
+            )),
+            None => report.push_str("  Day change -"),
+        }
+
+        // Only record complete valuations, a missing price would look like a crash in value
+        if missing.is_empty() {
+            self.record(now, total)?;
+        }
+        Ok(report)
+    }
+
+    /// Newest recorded value at or before `timestamp`
+    fn value_at_or_before(&self, timestamp: u64) -> Option<f64> {
+        self.history
+            .iter()
+            .rev()
+            .find(|(recorded, _)| *recorded <= timestamp)
+            .map(|(_, value)| *value)
+    }
+
+    /// Drops values older than the one day change needs, so memory stays at about a day of cycles
+    fn prune(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(DAY_SECS);
+        if let Some(keep_from) = self.history.iter().rposition(|(recorded, _)| *recorded <= cutoff) {
+            self.history.drain(..keep_from);
+        }
+    }
+
+    /// Appends one point to the portfolio-value time series
+    fn record(&mut self, timestamp: u64, total: f64) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.history_file)?;
+        writeln!(file, "{},{}", timestamp, total)?;
+        self.history.push((timestamp, total));
+        self.prune(timestamp);
+        Ok(())
+    }
+}
+
+/// Parses `asset,quantity,cost_basis` lines; a header line and blank lines are skipped.
+/// Asset names are matched case-insensitively and stored as configured.
+fn parse_holdings(contents: &str, assets: &[AssetConfig]) -> Result<Vec<Holding>, Box<dyn Error>> {
+    let mut holdings = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("asset,") {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 3 {
+            return Err(format!("line {}: expected asset,quantity,cost_basis", number + 1).into());
+        }
+        let asset = assets
+            .iter()
+            .find(|asset| asset.name.eq_ignore_ascii_case(fields[0]))
+            .ok_or_else(|| format!("line {}: no configured asset named '{}'", number + 1, fields[0]))?;
+        holdings.push(Holding {
+            asset: asset.name.clone(),
+            quantity: fields[1].parse().map_err(|e| format!("line {}: quantity: {}", number + 1, e))?,
+            cost_basis: fields[2].parse().map_err(|e| format!("line {}: cost_basis: {}", number + 1, e))?,
+        });
+    }
+    Ok(holdings)
+}
+
+/// Money amount with an explicit "+" for gains
+fn signed(fx: &mut Fx, amount: f64) -> String {
+    let formatted = fx.format_usd(amount);
+    if amount >= 0.0 {
+        format!("+{}", formatted)
+    } else {
+        formatted
+    }
+}
+
+/// `change` as a percentage of `base`, or "n/a" if there's no base
+fn percent(change: f64, base: f64) -> String {
+    if base > 0.0 {
+        format!("{:+.2}%", change / base * 100.0)
+    } else {
+        "n/a".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::SourceStatus;
+    use crate::config::{Config, DisplayConfig};
+    use std::{env, process};
+
+    /// An asset that only ever has one price
+    struct Quote(&'static str, f64);
+
+    impl Pricing for Quote {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn last_price(&self) -> Option<f64> {
+            Some(self.1)
+        }
+        fn last_updated(&self) -> Option<u64> {
+            Some(unix_now())
+        }
+        fn last_source(&self) -> Option<&str> {
+            Some("test")
+        }
+        fn next_poll(&self, now: u64) -> u64 {
+            now
+        }
+        fn set_interval(&mut self, _secs: u64) {}
+        fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>> {
+            Ok(self.1)
+        }
+        fn display(&self, _fx: &mut Fx) -> String {
+            String::new()
+        }
+        fn sources(&self) -> Vec<(&str, SourceStatus)> {
+            Vec::new()
+        }
+    }
+
+    fn usd() -> Fx {
+        Fx::new(&DisplayConfig::default()).unwrap()
+    }
+
+    /// Bitcoin 0.5 bought for 20000 and Ethereum 2 for 4000, recording into a fresh file
+    fn tracked(test: &str, history: Vec<(u64, f64)>) -> Portfolio {
+        let history_file = env::temp_dir().join(format!("fdf-portfolio-{}-{}.csv", test, process::id()));
+        let _ = fs::remove_file(&history_file);
+        let holdings = parse_holdings("asset,quantity,cost_basis\nbitcoin,0.5,20000\nEthereum,2,4000\n", &Config::default().assets).unwrap();
+        Portfolio { holdings, history_file: history_file.to_string_lossy().into_owned(), history }
+    }
+
+    fn quotes(bitcoin: f64, ethereum: f64) -> Vec<Box<dyn Pricing>> {
+        vec![Box::new(Quote("Bitcoin", bitcoin)), Box::new(Quote("Ethereum", ethereum))]
+    }
+
+    #[test]
+    fn values_positions_and_records_the_total() {
+        let mut portfolio = tracked("value", Vec::new());
+        let report = portfolio.update(&quotes(60000.0, 3000.0), &mut usd()).unwrap();
+
+        // 30000 + 6000 against 24000 paid
+        assert!(report.contains("Bitcoin      $30,000.00  83.3%  P&L +$10,000.00 (+50.00%)"), "{}", report);
+        assert!(report.contains("Ethereum      $6,000.00  16.7%  P&L +$2,000.00 (+50.00%)"), "{}", report);
+        assert!(report.contains("Total        $36,000.00         P&L +$12,000.00 (+50.00%)"), "{}", report);
+        assert_eq!(portfolio.history.last().map(|(_, value)| *value), Some(36000.0));
+        fs::remove_file(&portfolio.history_file).unwrap();
+    }
+
+    #[test]
+    fn incomplete_valuations_are_not_recorded() {
+        let mut portfolio = tracked("missing", Vec::new());
+        let assets: Vec<Box<dyn Pricing>> = vec![Box::new(Quote("Bitcoin", 60000.0))];
+        let report = portfolio.update(&assets, &mut usd()).unwrap();
+        assert!(report.contains("no price yet for Ethereum"), "{}", report);
+        assert!(portfolio.history.is_empty());
+        assert!(!Path::new(&portfolio.history_file).exists());
+    }
+
+    #[test]
+    fn day_change_needs_a_day_of_history() {
+        let now = unix_now();
+
+        // Only values from the last hour: nothing a day old to compare against
+        let mut portfolio = tracked("young", vec![(now - 3600, 30000.0)]);
+        let report = portfolio.update(&quotes(60000.0, 3000.0), &mut usd()).unwrap();
+        assert!(report.ends_with("Day change -"), "{}", report);
+        fs::remove_file(&portfolio.history_file).unwrap();
+
+        // A value from just over a day ago is the base, not the newer one
+        let mut portfolio = tracked("day", vec![(now - DAY_SECS - 60, 24000.0), (now - 3600, 30000.0)]);
+        let report = portfolio.update(&quotes(60000.0, 3000.0), &mut usd()).unwrap();
+        assert!(report.ends_with("Day change +$12,000.00 (+50.00%)"), "{}", report);
+        fs::remove_file(&portfolio.history_file).unwrap();
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_value_a_day_old() {
+        let mut portfolio = tracked("prune", vec![(100, 1.0), (200, 2.0), (300, 3.0), (DAY_SECS + 250, 4.0)]);
+        portfolio.prune(DAY_SECS + 250);
+        assert_eq!(portfolio.history, [(200, 2.0), (300, 3.0), (DAY_SECS + 250, 4.0)]);
+    }
+
+    #[test]
+    fn holdings_must_name_configured_assets() {
+        let error = parse_holdings("Dogecoin,100,10\n", &Config::default().assets).unwrap_err();
+        assert_eq!(error.to_string(), "line 1: no configured asset named 'Dogecoin'");
+    }
+}
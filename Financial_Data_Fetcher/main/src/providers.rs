@@ -0,0 +1,329 @@
+// Price providers: each one wraps a single upstream API
+use serde::Deserialize;  // For JSON deserialization
+use std::collections::HashMap; // For responses keyed by symbol
+use std::error::Error;   // For error handling
+use std::time::Duration; // For time intervals
+
+use crate::cache;
+use crate::config::ProviderConfig;
+use crate::credentials;
+use crate::http;
+use crate::log::warning;
+use crate::shutdown;
+use crate::validate;
+
+/// Currency every provider quotes in (display conversion happens later)
+pub const QUOTE_CURRENCY: &str = "USD";
+
+/// A single upstream API that can quote one asset
+pub trait Provider: Send {
+    /// Short name recorded next to every saved price (e.g. "coingecko")
+    fn name(&self) -> &str;
+
+    /// The configured symbol or coin id this provider quotes
+    fn symbol(&self) -> &str;
+
+    /// Endpoint that returns the current quote
+    fn url(&self) -> String;
+
+    /// Extracts the USD price from a response body, naming the field if the layout changed
+    fn parse(&self, body: &str) -> Result<f64, Box<dyn Error>>;
+
+    /// Sends the request, with the provider's API key if one is set and
+    /// If-None-Match when `etag` is given
+    fn request(&self, etag: Option<&str>) -> Result<ureq::Response, Box<dyn Error>> {
+        let credential = credentials::for_provider(self.name());
+        http::get(&self.url(), credential, etag)
+            .map_err(|e| credentials::redact(&e.to_string(), credential).into())
+    }
+
+    /// Fetches the current USD price, reusing a recent response for the same quote.
+    /// A body that doesn't parse or holds an implausible price is quarantined for inspection.
+    fn fetch(&self) -> Result<f64, Box<dyn Error>> {
+        let key = cache::key(self.name(), self.symbol(), QUOTE_CURRENCY);
+        let body = cache::fetch(&key, |etag| self.request(etag))?;
+        let result = self.parse(&body).and_then(|price| validate::check_price(self.name(), price));
+        if result.is_err() {
+            cache::invalidate(&key);
+            if let Some(path) = validate::quarantine(self.name(), self.symbol(), &body) {
+                warning!("{}: saved the rejected response to {}", self.name(), path.display());
+            }
+        }
+        result
+    }
+}
+
+/// Builds a provider from its config entry, rejecting unknown provider names
+pub fn build(config: &ProviderConfig) -> Result<Box<dyn Provider>, Box<dyn Error>> {
+    let symbol = config.symbol.clone();
+    match config.provider.as_str() {
+        "coingecko" => Ok(Box::new(CoinGecko { id: symbol })),
+        "yahoo" => Ok(Box::new(Yahoo { symbol })),
+        "coinbase" => Ok(Box::new(Coinbase { symbol })),
+        "kraken" => Ok(Box::new(Kraken { symbol })),
+        "binance" => Ok(Box::new(Binance { symbol })),
+        other => Err(format!("unknown provider '{}'", other).into()),
+    }
+}
+
+/// CoinGecko simple price API (crypto only, `symbol` is the coin id like "bitcoin")
+pub struct CoinGecko {
+    id: String,
+}
+
+impl Provider for CoinGecko {
+    fn name(&self) -> &str {
+        "coingecko"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.id
+    }
+
+    fn url(&self) -> String {
+        // Pro keys only work against the pro host
+        let host = match credentials::for_provider(self.name()).and_then(|c| c.plan.as_deref()) {
+            Some("pro") => "pro-api.coingecko.com",
+            _ => "api.coingecko.com",
+        };
+        format!(
+            "https://{}/api/v3/simple/price?ids={}&vs_currencies=usd",
+            host, self.id
+        )
+    }
+
+    fn parse(&self, body: &str) -> Result<f64, Box<dyn Error>> {
+        // Response looks like {"bitcoin": {"usd": 76617}}, keyed by coin id
+        #[derive(Deserialize)]
+        struct CoinPrice {
+            usd: f64,  // Price in USD
+        }
+
+        let mut response: HashMap<String, CoinPrice> = validate::from_json(self.name(), body)?;
+        match response.remove(&self.id) {
+            Some(price) => Ok(price.usd),
+            None => Err(format!("coingecko returned no price for '{}'", self.id).into()),
+        }
+    }
+
+    fn request(&self, etag: Option<&str>) -> Result<ureq::Response, Box<dyn Error>> {
+        let url = self.url();
+        let credential = credentials::for_provider(self.name());
+
+        // Make HTTP request with rate limit handling
+        match http::get(&url, credential, etag) {
+            Ok(resp) => Ok(resp),
+            // If rate limited (429), wait 60 seconds and retry; a shutdown cuts the wait short
+            Err(e) if matches!(*e, ureq::Error::Status(429, _)) => {
+                warning!("Rate limited, waiting 60 seconds...");
+                if !shutdown::sleep(Duration::from_secs(60)) {
+                    return Err("coingecko rate limited, retry abandoned for shutdown".into());
+                }
+                http::get(&url, credential, etag).map_err(|e| credentials::redact(&e.to_string(), credential).into())
+            }
+            // Propagate other errors
+            Err(e) => Err(credentials::redact(&e.to_string(), credential).into()),
+        }
+    }
+}
+
+/// Yahoo Finance chart API (`symbol` is a ticker like "^GSPC" or "BTC-USD")
+pub struct Yahoo {
+    symbol: String,
+}
+
+impl Provider for Yahoo {
+    fn name(&self) -> &str {
+        "yahoo"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn url(&self) -> String {
+        // "^" has to be escaped in the URL path
+        format!(
+            "https://query1.finance.yahoo.com/v8/finance/chart/{}",
+            self.symbol.replace('^', "%5E")
+        )
+    }
+
+    fn parse(&self, body: &str) -> Result<f64, Box<dyn Error>> {
+        // Complex nested structures to match Yahoo's JSON response
+        #[derive(Deserialize)]
+        struct YahooResponse {
+            chart: Chart,
+        }
+
+        #[derive(Deserialize)]
+        struct Chart {
+            result: Vec<ChartResult>,  // Array of results (we take first)
+        }
+
+        #[derive(Deserialize)]
+        struct ChartResult {
+            meta: Meta,  // Metadata containing price
+        }
+
+        #[derive(Deserialize)]
+        struct Meta {
+            #[serde(rename = "regularMarketPrice")]  // Map JSON field to Rust naming
+            regular_market_price: f64,
+        }
+
+        // Extract price from nested structure
+        let response: YahooResponse = validate::from_json(self.name(), body)?;
+        match response.chart.result.first() {
+            Some(result) => Ok(result.meta.regular_market_price),
+            None => Err(format!("yahoo returned no result for '{}'", self.symbol).into()),
+        }
+    }
+}
+
+/// Coinbase spot price API (`symbol` is the base currency like "BTC")
+pub struct Coinbase {
+    symbol: String,
+}
+
+impl Coinbase {
+    /// Coinbase quotes currency pairs as "BTC-USD"
+    fn pair(&self) -> String {
+        format!("{}-USD", self.symbol.to_uppercase())
+    }
+}
+
+impl Provider for Coinbase {
+    fn name(&self) -> &str {
+        "coinbase"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn url(&self) -> String {
+        format!("https://api.coinbase.com/v2/prices/{}/spot", self.pair())
+    }
+
+    fn parse(&self, body: &str) -> Result<f64, Box<dyn Error>> {
+        // {"data": {"amount": "76617.12", "base": "BTC", "currency": "USD"}}
+        #[derive(Deserialize)]
+        struct CoinbaseResponse {
+            data: SpotPrice,
+        }
+
+        #[derive(Deserialize)]
+        struct SpotPrice {
+            amount: String,  // Coinbase sends prices as strings
+            currency: String,
+        }
+
+        let response: CoinbaseResponse = validate::from_json(self.name(), body)?;
+        if response.data.currency != "USD" {
+            return Err(format!("coinbase quoted {} instead of USD", response.data.currency).into());
+        }
+        validate::number(self.name(), "data.amount", &response.data.amount)
+    }
+}
+
+/// Kraken public Ticker API (`symbol` is the base currency like "BTC")
+pub struct Kraken {
+    symbol: String,
+}
+
+impl Kraken {
+    /// Kraken calls bitcoin "XBT" and answers with its own pair names
+    fn pair(&self) -> String {
+        let base = match self.symbol.to_uppercase().as_str() {
+            "BTC" => "XBT".to_string(),
+            other => other.to_string(),
+        };
+        format!("{}USD", base)
+    }
+}
+
+impl Provider for Kraken {
+    fn name(&self) -> &str {
+        "kraken"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn url(&self) -> String {
+        format!("https://api.kraken.com/0/public/Ticker?pair={}", self.pair())
+    }
+
+    fn parse(&self, body: &str) -> Result<f64, Box<dyn Error>> {
+        // {"error": [], "result": {"XXBTZUSD": {"c": ["76617.10000", "0.0015"], ...}}}
+        #[derive(Deserialize)]
+        struct KrakenResponse {
+            error: Vec<String>,
+            #[serde(default)]
+            result: HashMap<String, Ticker>,
+        }
+
+        #[derive(Deserialize)]
+        struct Ticker {
+            c: Vec<String>,  // Last trade closed: [price, lot volume]
+        }
+
+        let response: KrakenResponse = validate::from_json(self.name(), body)?;
+        if !response.error.is_empty() {
+            return Err(format!("kraken error: {}", response.error.join(", ")).into());
+        }
+
+        // The result key is Kraken's canonical name (XBTUSD -> XXBTZUSD), so take the only entry
+        let ticker = match response.result.into_values().next() {
+            Some(ticker) => ticker,
+            None => return Err(format!("kraken returned no ticker for '{}'", self.pair()).into()),
+        };
+        match ticker.c.first() {
+            Some(price) => validate::number(self.name(), "result.*.c[0]", price),
+            None => Err("kraken ticker has no last trade price".into()),
+        }
+    }
+}
+
+/// Binance ticker/price API (`symbol` is the base currency like "BTC")
+pub struct Binance {
+    symbol: String,
+}
+
+impl Binance {
+    /// Binance has no USD book for most coins, USDT is the closest quote currency
+    fn pair(&self) -> String {
+        format!("{}USDT", self.symbol.to_uppercase())
+    }
+}
+
+impl Provider for Binance {
+    fn name(&self) -> &str {
+        "binance"
+    }
+
+    fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn url(&self) -> String {
+        format!("https://api.binance.com/api/v3/ticker/price?symbol={}", self.pair())
+    }
+
+    fn parse(&self, body: &str) -> Result<f64, Box<dyn Error>> {
+        // {"symbol": "BTCUSDT", "price": "76617.12000000"}
+        #[derive(Deserialize)]
+        struct TickerPrice {
+            symbol: String,
+            price: String,
+        }
+
+        let response: TickerPrice = validate::from_json(self.name(), body)?;
+        if response.symbol != self.pair() {
+            return Err(format!("binance answered for {} instead of {}", response.symbol, self.pair()).into());
+        }
+        validate::number(self.name(), "price", &response.price)
+    }
+}
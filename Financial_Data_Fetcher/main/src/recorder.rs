@@ -0,0 +1,218 @@
+// Storage subscriber: appends every published price to its asset's price file
+use std::collections::{HashMap, HashSet}; // For stores by asset name
+use std::error::Error;   // For error handling
+
+use crate::calendar::MarketCalendar;
+use crate::config::{AssetConfig, Config, StorageConfig};
+use crate::events::{PriceUpdate, Subscriber};
+use crate::log::warning;
+use crate::storage::{PriceRecord, PriceStore};
+
+/// Price files for every configured asset
+pub struct Recorder {
+    assets: HashMap<String, Recording>,
+    storage: StorageConfig,  // Rotation, retention and batching of every file
+}
+
+/// One asset's file and what was last written to it
+struct Recording {
+    file: String,                     // Configured file name, to notice it changing on reload
+    schedule: Option<String>,         // Configured market calendar, likewise
+    store: PriceStore,
+    calendar: Option<MarketCalendar>, // Set for scheduled assets, whose records carry the session
+    dedupe: Option<u64>,              // Heartbeat seconds when only changed prices are stored
+    last_saved: Option<(u64, f64)>,   // Timestamp and price of the last record written
+}
+
+impl Recorder {
+    /// Opens the price files of every asset in the config
+    pub fn from_config(config: &Config) -> Result<Recorder, Box<dyn Error>> {
+        let mut recorder = Recorder {
+            assets: HashMap::new(),
+            storage: config.storage.clone(),
+        };
+        for asset in &config.assets {
+            recorder.add(asset)?;
+        }
+        Ok(recorder)
+    }
+
+    /// Starts recording one asset, picking up after its newest saved record
+    pub fn add(&mut self, asset: &AssetConfig) -> Result<(), Box<dyn Error>> {
+        let storage = &self.storage;
+        let store = PriceStore::new(&asset.file, storage);
+        store.recover();  // This is the writer, so a torn record from a crash can be cut off
+        let last_saved = store.last_record()?.map(|record| (record.timestamp, record.price));
+        let calendar = match &asset.schedule {
+            Some(name) => Some(MarketCalendar::by_name(name)?),
+            None => None,
+        };
+        self.assets.insert(
+            asset.name.clone(),
+            Recording {
+                file: asset.file.clone(),
+                schedule: asset.schedule.clone(),
+                store,
+                calendar,
+                dedupe: storage.store_on_change.then_some(storage.heartbeat_secs),
+                last_saved,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stops recording an asset, writing out anything still buffered
+    pub fn remove(&mut self, name: &str) {
+        if let Some(mut recording) = self.assets.remove(name) {
+            if let Err(e) = recording.store.flush() {
+                warning!("Error flushing {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Appends one record for the update's asset (updates for unknown assets are ignored)
+    fn save(&mut self, update: &PriceUpdate) -> Result<(), Box<dyn Error>> {
+        let Some(recording) = self.assets.get_mut(&update.asset) else {
+            return Ok(());
+        };
+
+        // One CSV record per price: unix timestamp, price, source (and session for scheduled assets)
+        let record = PriceRecord {
+            timestamp: update.timestamp,
+            price: update.price,
+            source: update.source.clone(),
+            session: recording
+                .calendar
+                .as_ref()
+                .map(|calendar| calendar.session_at(update.timestamp).tag().to_string()),
+        };
+
+        // In store-on-change mode an unchanged price is only written as a heartbeat
+        if let (Some(heartbeat), Some((saved_at, saved_price))) = (recording.dedupe, recording.last_saved) {
+            if update.price == saved_price && record.timestamp.saturating_sub(saved_at) < heartbeat {
+                return Ok(());
+            }
+        }
+
+        recording.store.append(record.timestamp, &record.to_line())?;
+        recording.last_saved = Some((record.timestamp, update.price));
+        Ok(())
+    }
+}
+
+impl Subscriber for Recorder {
+    fn on_update(&mut self, update: &PriceUpdate) {
+        if let Err(e) = self.save(update) {
+            warning!("Error saving {} price: {}", update.asset, e);
+        }
+    }
+
+    fn flush(&mut self) {
+        for (name, recording) in &mut self.assets {
+            if let Err(e) = recording.store.flush() {
+                warning!("Error flushing {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Writes out records that have waited flush_interval_secs, even if no new price came in
+    fn tick(&mut self) {
+        for (name, recording) in &mut self.assets {
+            if let Err(e) = recording.store.flush_if_due() {
+                warning!("Error flushing {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Opens files for added assets, closes those of removed ones and reopens changed ones
+    fn reconfigure(&mut self, config: &Config) {
+        // Storage settings apply to every file, so a change reopens all of them
+        if config.storage != self.storage {
+            self.flush();
+            self.assets.clear();
+            self.storage = config.storage.clone();
+        }
+
+        let names: HashSet<&str> = config.assets.iter().map(|asset| asset.name.as_str()).collect();
+        let removed: Vec<String> = self.assets.keys().filter(|name| !names.contains(name.as_str())).cloned().collect();
+        for name in removed {
+            self.remove(&name);
+        }
+
+        for asset in &config.assets {
+            let unchanged = self
+                .assets
+                .get(&asset.name)
+                .is_some_and(|recording| recording.file == asset.file && recording.schedule == asset.schedule);
+            if !unchanged {
+                self.remove(&asset.name);
+                if let Err(e) = self.add(asset) {
+                    warning!("Error opening price file for {}: {}", asset.name, e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::{env, fs, process};
+
+    /// Bitcoin recorded into a fresh directory, storing only changed prices if `store_on_change`
+    fn config(test: &str, store_on_change: bool) -> (Config, PathBuf) {
+        let dir = env::temp_dir().join(format!("fdf-recorder-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut config = Config::default();
+        config.assets.truncate(1);
+        config.assets[0].file = dir.join("btc.csv").to_string_lossy().into_owned();
+        config.storage.fsync = false;
+        config.storage.store_on_change = store_on_change;
+        config.storage.heartbeat_secs = 300;
+        (config, dir)
+    }
+
+    fn update(timestamp: u64, price: f64) -> PriceUpdate {
+        PriceUpdate { asset: "Bitcoin".to_string(), price, timestamp, source: "test".to_string() }
+    }
+
+    fn saved(config: &Config) -> Vec<(u64, f64)> {
+        let store = PriceStore::new(&config.assets[0].file, &config.storage);
+        store.history().unwrap().iter().map(|record| (record.timestamp, record.price)).collect()
+    }
+
+    #[test]
+    fn unchanged_prices_are_only_saved_as_heartbeats() {
+        let (config, dir) = config("dedupe", true);
+        let mut recorder = Recorder::from_config(&config).unwrap();
+        for (timestamp, price) in [(1000, 100.0), (1010, 100.0), (1020, 101.0), (1030, 101.0), (1320, 101.0)] {
+            recorder.on_update(&update(timestamp, price));
+        }
+        recorder.flush();
+        assert_eq!(saved(&config), [(1000, 100.0), (1020, 101.0), (1320, 101.0)]);
+
+        // A new recorder compares against the newest saved record, not against nothing
+        drop(recorder);
+        let mut recorder = Recorder::from_config(&config).unwrap();
+        recorder.on_update(&update(1330, 101.0));
+        recorder.on_update(&update(1340, 102.0));
+        recorder.flush();
+        assert_eq!(saved(&config), [(1000, 100.0), (1020, 101.0), (1320, 101.0), (1340, 102.0)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_price_is_saved_without_store_on_change() {
+        let (config, dir) = config("every", false);
+        let mut recorder = Recorder::from_config(&config).unwrap();
+        for timestamp in [1000, 1010, 1020] {
+            recorder.on_update(&update(timestamp, 100.0));
+        }
+        recorder.on_update(&PriceUpdate { asset: "Dogecoin".to_string(), ..update(1030, 1.0) });
+        recorder.flush();
+        assert_eq!(saved(&config), [(1000, 100.0), (1010, 100.0), (1020, 100.0)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+// Hot reload of config.json: notice edits (or SIGHUP), validate, and apply asset changes in place
+use std::collections::HashMap; // For running assets by name
+use std::error::Error;   // For error handling
+use std::fmt;            // For printing what a reload changed
+use std::fs;             // For the config file's mtime
+use std::path::{Path, PathBuf}; // For the watched path
+use std::time::SystemTime; // For modification times
+
+use crate::asset::{unix_now, Asset, Pricing};
+use crate::config::{AssetConfig, Config};
+use crate::fetcher::Scheduler;
+use crate::shutdown;
+
+/// Watches the config file for edits, and the process for SIGHUP
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>, // Last seen mtime (None while the file doesn't exist)
+}
+
+impl ConfigWatcher {
+    /// Starts watching `path`; the file as it is now counts as seen
+    pub fn new(path: &str) -> ConfigWatcher {
+        let path = PathBuf::from(path);
+        let modified = modified(&path);
+        ConfigWatcher { path, modified }
+    }
+
+    /// Turns an edit since the last check into a reload request, the same as SIGHUP, so a
+    /// sleeping `Scheduler::wait` ends. Cheap enough to call every tick.
+    /// A deleted file is not a change: the running config stays.
+    pub fn poll(&mut self) {
+        let modified = modified(&self.path);
+        if modified.is_some() && modified != self.modified {
+            shutdown::request_reload();
+        }
+        self.modified = modified;
+    }
+
+    /// True if the file was written or created since the last call, or a reload was signalled
+    pub fn changed(&mut self) -> bool {
+        self.poll();
+        shutdown::take_reload()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Loads and validates the config for a reload; unlike startup, a missing file is an error
+pub fn load(path: &str) -> Result<Config, Box<dyn Error>> {
+    if !Path::new(path).exists() {
+        return Err(format!("{} not found", path).into());
+    }
+    Config::load(path)
+}
+
+/// Asset names a reload touched
+#[derive(Debug, Clone, Default)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub rebuilt: Vec<String>,  // Settings changed, so the asset was built again from its config
+    pub retimed: Vec<String>,  // Only interval_secs changed, state kept
+    pub unapplied: Vec<&'static str>, // Other sections that changed but need a restart
+}
+
+impl fmt::Display for ReloadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("rebuilt", &self.rebuilt),
+            ("new interval", &self.retimed),
+        ]
+        .iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(label, names)| format!("{} {}", label, names.join(", ")))
+        .collect();
+        if parts.is_empty() {
+            write!(f, "no asset changes")
+        } else {
+            write!(f, "{}", parts.join("; "))
+        }
+    }
+}
+
+/// What happens to one asset of the new config
+enum Change {
+    Keep(usize),             // Unchanged, index in the running list
+    Retime(usize),           // Same apart from interval_secs
+    Build(Box<dyn Pricing>), // New or changed, freshly built
+}
+
+/// Applies `new` to the running assets and scheduler, then makes it the current config.
+///
+/// Unchanged assets keep their prices, breakers and feeds. Assets whose only change is
+/// `interval_secs` keep them too and are rescheduled. Added or otherwise changed assets are
+/// built from their config (resuming from their price files) and fetched straight away; removed
+/// ones are dropped. Every new asset is built before anything changes, so a failure leaves the
+/// running set as it was. Subscribers get the new config too, so the recorder follows the asset
+/// list and `storage`. Other sections that changed are listed in `unapplied`: they need a
+/// restart (`breaker` already applies to the assets built here, not to the ones kept).
+pub fn apply(
+    current: &mut Config,
+    new: Config,
+    assets: &mut Vec<Box<dyn Pricing>>,
+    scheduler: &mut Scheduler,
+) -> Result<ReloadSummary, Box<dyn Error>> {
+    let running: HashMap<&str, usize> = assets
+        .iter()
+        .enumerate()
+        .map(|(index, asset)| (asset.name(), index))
+        .collect();
+    let old: HashMap<&str, &AssetConfig> = current.assets.iter().map(|asset| (asset.name.as_str(), asset)).collect();
+
+    let mut summary = ReloadSummary::default();
+    let mut changes = Vec::new();
+    for config in &new.assets {
+        let change = match (old.get(config.name.as_str()), running.get(config.name.as_str())) {
+            (Some(&before), Some(&index)) if before == config => Change::Keep(index),
+            (Some(&before), Some(&index)) if same_but_interval(before, config) => {
+                summary.retimed.push(config.name.clone());
+                Change::Retime(index)
+            }
+            (before, _) => {
+                let asset = Asset::from_config(config, &new.storage, &new.breaker)
+                    .map_err(|e| format!("{}: {}", config.name, e))?;
+                match before {
+                    Some(_) => summary.rebuilt.push(config.name.clone()),
+                    None => summary.added.push(config.name.clone()),
+                }
+                Change::Build(Box::new(asset))
+            }
+        };
+        changes.push(change);
+    }
+    summary.removed = current
+        .assets
+        .iter()
+        .filter(|asset| !new.assets.iter().any(|other| other.name == asset.name))
+        .map(|asset| asset.name.clone())
+        .collect();
+
+    // Nothing can fail from here on
+    let mut previous: Vec<Option<Box<dyn Pricing>>> = assets.drain(..).map(Some).collect();
+    let mut order = Vec::new();
+    let mut retimed = Vec::new();
+    for (config, change) in new.assets.iter().zip(changes) {
+        let asset = match change {
+            Change::Keep(index) => {
+                order.push(Some(index));
+                previous[index].take()
+            }
+            Change::Retime(index) => {
+                order.push(Some(index));
+                retimed.push(assets.len());
+                previous[index].take().map(|mut asset| {
+                    asset.set_interval(config.interval_secs);
+                    asset
+                })
+            }
+            Change::Build(asset) => {
+                order.push(None);
+                Some(asset)
+            }
+        };
+        assets.push(asset.expect("asset names are unique"));
+    }
+    scheduler.rearrange(&order);
+
+    // A shorter interval takes effect now, a longer one after the fetch already scheduled
+    let now = unix_now();
+    for index in retimed {
+        let due = scheduler.due(index).min(assets[index].next_poll(now));
+        scheduler.set_due(index, due);
+    }
+
+    scheduler.reconfigure(&new);
+    summary.unapplied = unapplied(current, &new);
+    *current = new;
+    Ok(summary)
+}
+
+/// Sections a reload doesn't apply that differ between the two configs
+fn unapplied(before: &Config, after: &Config) -> Vec<&'static str> {
+    [
+        ("display", before.display != after.display),
+        ("portfolio", before.portfolio != after.portfolio),
+        ("http", before.http != after.http),
+        ("credentials", before.credentials != after.credentials || before.secrets_file != after.secrets_file),
+        ("cache", before.cache != after.cache),
+        ("quarantine_dir", before.quarantine_dir != after.quarantine_dir),
+        ("breaker", before.breaker != after.breaker),
+        ("daemon", before.daemon != after.daemon),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(section, _)| section)
+    .collect()
+}
+
+/// True if the two configs differ in nothing but interval_secs
+fn same_but_interval(before: &AssetConfig, after: &AssetConfig) -> bool {
+    let mut before = before.clone();
+    before.interval_secs = after.interval_secs;
+    &before == after
+}
@@ -0,0 +1,72 @@
+// Graceful shutdown: Ctrl-C, SIGTERM (or the dashboard's quit key) stop the loop so buffers get
+// flushed. SIGHUP asks for the config to be reloaded instead.
+use std::io;             // For handler installation errors
+use std::sync::atomic::{AtomicBool, Ordering}; // Flags shared with the signal handlers
+use std::sync::{Arc, LazyLock}; // signal-hook sets flags it shares ownership of
+use std::thread;         // For sleep functionality
+use std::time::{Duration, Instant}; // For interruptible sleeps
+
+/// Set once a shutdown has been asked for
+static REQUESTED: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+
+/// Set by SIGHUP until the reload is picked up
+static RELOAD: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+
+/// How often sleeping loops check the flag
+const POLL: Duration = Duration::from_millis(200);
+
+/// Installs the Ctrl-C / SIGTERM handlers, and SIGHUP for reloads on Unix
+pub fn install_handler() -> io::Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    signal_hook::flag::register(SIGINT, Arc::clone(&REQUESTED))?;
+    signal_hook::flag::register(SIGTERM, Arc::clone(&REQUESTED))?;
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&RELOAD))?;
+    Ok(())
+}
+
+/// Asks every loop to stop at the next opportunity
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// True once a shutdown has been requested
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Sleeps for `duration`, waking early on shutdown. Returns false if interrupted.
+pub fn sleep(duration: Duration) -> bool {
+    sleep_while(duration, || true)
+}
+
+/// Like `sleep`, but also wakes early (returning true) when a reload is pending
+pub fn sleep_or_reload(duration: Duration) -> bool {
+    sleep_while(duration, || !RELOAD.load(Ordering::SeqCst))
+}
+
+/// True once after each SIGHUP (or `request_reload`), then false until the next
+pub fn take_reload() -> bool {
+    RELOAD.swap(false, Ordering::SeqCst)
+}
+
+/// True while a reload is waiting to be picked up, without taking it
+pub fn reload_pending() -> bool {
+    RELOAD.load(Ordering::SeqCst)
+}
+
+/// Asks for a config reload, the same as SIGHUP
+pub fn request_reload() {
+    RELOAD.store(true, Ordering::SeqCst);
+}
+
+fn sleep_while(duration: Duration, keep_sleeping: impl Fn() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < duration && keep_sleeping() {
+        if requested() {
+            return false;
+        }
+        thread::sleep(POLL.min(duration.saturating_sub(start.elapsed())));
+    }
+    !requested()
+}
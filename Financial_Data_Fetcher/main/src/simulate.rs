@@ -0,0 +1,391 @@
+// Simulation mode: stored history or a seeded random walk fed through the normal loop, offline
+// and at accelerated speed, so display, storage and subscribers can be tried end to end
+use std::collections::VecDeque; // For the prices still to replay
+use std::error::Error;   // For error handling
+use std::fs;             // For preparing the output directory
+use std::path::{Path, PathBuf}; // For output paths
+
+use crate::asset::{format_unix, unix_now, Pricing, SourceStatus};
+use crate::config::{AssetConfig, Config, SimulationConfig, StorageConfig};
+use crate::fx::Fx;
+use crate::storage::PriceStore;
+use crate::stream::RandomWalk;
+
+/// Where simulated prices come from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Replay,  // Each asset's stored history, in timestamp order
+    Walk,    // A seeded random walk from the last stored price, one step per interval_secs
+}
+
+impl Mode {
+    /// Looks up a mode by its command line name ("replay" or "walk")
+    pub fn by_name(name: &str) -> Result<Mode, Box<dyn Error>> {
+        match name.to_lowercase().as_str() {
+            "replay" => Ok(Mode::Replay),
+            "walk" | "random" => Ok(Mode::Walk),
+            other => Err(format!("unknown simulation mode '{}' (use replay or walk)", other).into()),
+        }
+    }
+
+    /// Source recorded with simulated prices
+    pub fn source(&self) -> &'static str {
+        match self {
+            Mode::Replay => "replay",
+            Mode::Walk => "walk",
+        }
+    }
+}
+
+/// Maps simulated time onto the wall clock
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    start: u64,       // Simulated unix time the run starts at
+    wall_start: u64,  // Real unix time it started
+    speed: f64,       // Simulated seconds per real second (0 = no pacing)
+}
+
+impl Clock {
+    pub fn new(start: u64, speed: f64) -> Clock {
+        Clock { start, wall_start: unix_now(), speed }
+    }
+
+    /// Real unix time at which the simulation reaches `time`
+    pub fn wall_time(&self, time: u64) -> u64 {
+        if self.speed <= 0.0 {
+            return 0;  // Always due
+        }
+        self.wall_start + (time.saturating_sub(self.start) as f64 / self.speed).ceil() as u64
+    }
+}
+
+/// The prices one asset hands out, one per fetch
+enum Feed {
+    Replay(VecDeque<(u64, f64)>),
+    Walk {
+        walk: RandomWalk,
+        next: u64,              // Simulated time of the next step
+        interval_secs: u64,     // Simulated seconds between steps
+        remaining: Option<u64>, // Steps left (None = endless)
+    },
+}
+
+/// An asset whose `fetch_price` reads from a simulation instead of the providers
+pub struct SimulatedAsset {
+    name: String,
+    mode: Mode,
+    feed: Feed,
+    clock: Clock,
+    last_price: Option<f64>,
+    last_updated: Option<u64>,  // Simulated time of last_price
+}
+
+impl SimulatedAsset {
+    /// What the asset will play, e.g. "replaying 120 stored prices", for printing before the run
+    pub fn plan(&self) -> String {
+        match &self.feed {
+            Feed::Replay(prices) => format!("replaying {} stored prices", prices.len()),
+            Feed::Walk { walk, .. } => format!("random walk from {:.2}", walk.price()),
+        }
+    }
+
+    /// Simulated time of the next price (None once finished)
+    fn next_time(&self) -> Option<u64> {
+        match &self.feed {
+            Feed::Replay(prices) => prices.front().map(|(timestamp, _)| *timestamp),
+            Feed::Walk { remaining: Some(0), .. } => None,
+            Feed::Walk { next, .. } => Some(*next),
+        }
+    }
+}
+
+impl Pricing for SimulatedAsset {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn last_price(&self) -> Option<f64> {
+        self.last_price
+    }
+
+    fn last_updated(&self) -> Option<u64> {
+        self.last_updated
+    }
+
+    fn last_source(&self) -> Option<&str> {
+        self.last_price.map(|_| self.mode.source())
+    }
+
+    fn next_poll(&self, _now: u64) -> u64 {
+        match self.next_time() {
+            Some(time) => self.clock.wall_time(time),
+            None => u64::MAX,
+        }
+    }
+
+    fn set_interval(&mut self, secs: u64) {
+        if let Feed::Walk { interval_secs, .. } = &mut self.feed {
+            *interval_secs = secs;
+        }
+    }
+
+    fn fetch_price(&mut self) -> Result<f64, Box<dyn Error>> {
+        let (timestamp, price) = match &mut self.feed {
+            Feed::Replay(prices) => prices.pop_front(),
+            Feed::Walk { remaining: Some(0), .. } => None,
+            Feed::Walk { walk, next, interval_secs, remaining } => {
+                let step = (*next, walk.step());
+                *next += *interval_secs;
+                if let Some(remaining) = remaining {
+                    *remaining -= 1;
+                }
+                Some(step)
+            }
+        }
+        .ok_or_else(|| format!("{}: simulation finished", self.name))?;
+        self.last_price = Some(price);
+        self.last_updated = Some(timestamp);
+        Ok(price)
+    }
+
+    fn display(&self, fx: &mut Fx) -> String {
+        format!(
+            "{}: {} (via {}, {})",
+            self.name,
+            fx.format_usd(self.last_price.unwrap_or(0.0)),
+            self.mode.source(),
+            self.last_updated.map(format_unix).unwrap_or_else(|| "-".to_string())
+        )
+    }
+
+    fn sources(&self) -> Vec<(&str, SourceStatus)> {
+        let status = match self.last_price {
+            Some(_) => SourceStatus::Ok,
+            None => SourceStatus::Untried,
+        };
+        vec![(self.mode.source(), status)]
+    }
+
+    fn finished(&self) -> bool {
+        self.next_time().is_none()
+    }
+}
+
+/// Builds a simulated asset for every configured one, reading the price files as they are now
+pub fn build_assets(config: &Config, mode: Mode) -> Result<Vec<SimulatedAsset>, Box<dyn Error>> {
+    let settings = &config.simulation;
+    let mut feeds = Vec::new();
+    let start = match mode {
+        Mode::Replay => {
+            for asset in &config.assets {
+                let history = PriceStore::new(&asset.file, &config.storage)
+                    .history()
+                    .map_err(|e| format!("{}: {}", asset.name, e))?;
+                feeds.push(Feed::Replay(history.iter().map(|record| (record.timestamp, record.price)).collect()));
+            }
+            // All assets share one timeline starting at the oldest record
+            feeds
+                .iter()
+                .filter_map(|feed| match feed {
+                    Feed::Replay(prices) => prices.front().map(|(timestamp, _)| *timestamp),
+                    Feed::Walk { .. } => None,
+                })
+                .min()
+                .ok_or("no stored prices to replay")?
+        }
+        Mode::Walk => {
+            let start = unix_now();
+            for asset in &config.assets {
+                feeds.push(walk(asset, config, settings, start)?);
+            }
+            start
+        }
+    };
+
+    let clock = Clock::new(start, settings.speed);
+    Ok(config
+        .assets
+        .iter()
+        .zip(feeds)
+        .map(|(asset, feed)| SimulatedAsset {
+            name: asset.name.clone(),
+            mode,
+            feed,
+            clock,
+            last_price: None,
+            last_updated: None,
+        })
+        .collect())
+}
+
+/// A random walk for one asset, starting from its newest stored price
+fn walk(asset: &AssetConfig, config: &Config, settings: &SimulationConfig, start: u64) -> Result<Feed, Box<dyn Error>> {
+    let last = PriceStore::new(&asset.file, &config.storage).last_record()?;
+    let price = last.map(|record| record.price).unwrap_or(settings.start_price);
+    Ok(Feed::Walk {
+        // Each asset gets its own sequence, the same on every run with this seed
+        walk: RandomWalk::seeded(price, settings.seed ^ name_hash(&asset.name), settings.volatility_pct),
+        next: start,
+        interval_secs: asset.interval_secs,
+        remaining: settings.steps,
+    })
+}
+
+/// FNV-1a of the asset name, stable across builds unlike the std hasher
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Points the price files and portfolio history at the simulation directory, removing what an
+/// earlier run left there, so simulated prices never mix with real ones. Returns the directory.
+pub fn redirect(config: &mut Config) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = PathBuf::from(&config.simulation.dir);
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let canonical = dir.canonicalize()?;
+
+    let storage = config.storage.clone();
+    let mut files: Vec<&mut String> = config.assets.iter_mut().map(|asset| &mut asset.file).collect();
+    if let Some(portfolio) = &mut config.portfolio {
+        files.push(&mut portfolio.history);
+    }
+    for file in files {
+        let original = Path::new(file.as_str());
+        let parent = match original.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        if parent.canonicalize().is_ok_and(|parent| parent == canonical) {
+            return Err(format!("simulation dir {} holds the real {}", dir.display(), file).into());
+        }
+        let name = original.file_name().ok_or_else(|| format!("{}: not a file name", file))?;
+        let redirected = dir.join(name);
+        remove_previous(&redirected, &storage)?;
+        *file = redirected.to_string_lossy().into_owned();
+    }
+    Ok(dir)
+}
+
+/// Removes a file from an earlier run along with the rotated and compressed files the store
+/// made from it; nothing else in the directory is touched
+fn remove_previous(path: &Path, storage: &StorageConfig) -> Result<(), Box<dyn Error>> {
+    let mut files = PriceStore::new(&path.to_string_lossy(), storage).files();
+    if path.exists() && !files.iter().any(|file| file == path) {
+        files.push(path.to_path_buf());  // A name without an extension isn't the store's base file
+    }
+    for file in files {
+        fs::remove_file(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::PriceRecord;
+    use std::{env, process};
+
+    /// Bitcoin and Ethereum with price files in a fresh directory
+    fn config(test: &str) -> (Config, PathBuf) {
+        let dir = env::temp_dir().join(format!("fdf-simulate-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut config = Config::default();
+        config.assets.truncate(2);
+        config.storage.fsync = false;
+        for asset in &mut config.assets {
+            asset.file = dir.join(&asset.file).to_string_lossy().into_owned();
+        }
+        (config, dir)
+    }
+
+    fn store(config: &Config, index: usize, prices: &[(u64, f64)]) {
+        let mut store = PriceStore::new(&config.assets[index].file, &config.storage);
+        for (timestamp, price) in prices {
+            let record = PriceRecord { timestamp: *timestamp, price: *price, source: "test".to_string(), session: None };
+            store.append(*timestamp, &record.to_line()).unwrap();
+        }
+    }
+
+    fn run(asset: &mut SimulatedAsset) -> Vec<(u64, f64)> {
+        let mut prices = Vec::new();
+        while !asset.finished() {
+            let price = asset.fetch_price().unwrap();
+            prices.push((asset.last_updated().unwrap(), price));
+        }
+        assert!(asset.fetch_price().is_err());
+        prices
+    }
+
+    #[test]
+    fn replay_follows_timestamps_on_one_timeline() {
+        let (mut config, dir) = config("replay");
+        store(&config, 0, &[(1300, 3.0), (1100, 1.0)]);
+        store(&config, 1, &[(1200, 2.0)]);
+        config.simulation.speed = 1.0;
+
+        let mut assets = build_assets(&config, Mode::Replay).unwrap();
+        assert_eq!(assets[0].plan(), "replaying 2 stored prices");
+
+        // Both assets are paced from the oldest record of either
+        let now = unix_now();
+        assert_eq!(assets[1].next_poll(now) - assets[0].next_poll(now), 100);
+
+        assert_eq!(run(&mut assets[0]), [(1100, 1.0), (1300, 3.0)]);
+        assert_eq!(run(&mut assets[1]), [(1200, 2.0)]);
+        assert_eq!(assets[0].next_poll(now), u64::MAX);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn walk_repeats_for_a_seed() {
+        let (mut config, dir) = config("walk");
+        store(&config, 0, &[(1000, 80000.0)]);
+        config.simulation.steps = Some(5);
+
+        let walk = |config: &Config| -> Vec<Vec<f64>> {
+            build_assets(config, Mode::Walk)
+                .unwrap()
+                .iter_mut()
+                .map(|asset| run(asset).into_iter().map(|(_, price)| price).collect())
+                .collect()
+        };
+        let first = walk(&config);
+        assert_eq!(first, walk(&config));
+        assert_eq!(first[0].len(), 5);
+        assert_ne!(first[0], first[1], "assets share a sequence");
+
+        // Bitcoin starts from its stored price, Ethereum (nothing stored) from start_price
+        let assets = build_assets(&config, Mode::Walk).unwrap();
+        assert_eq!(assets[0].plan(), "random walk from 80000.00");
+        assert_eq!(assets[1].plan(), format!("random walk from {:.2}", config.simulation.start_price));
+
+        config.simulation.seed += 1;
+        assert_ne!(walk(&config), first);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn redirect_moves_files_and_clears_only_earlier_output() {
+        let (mut config, dir) = config("redirect");
+        let simulation = dir.join("simulation");
+        fs::create_dir_all(&simulation).unwrap();
+        for name in ["bitcoin_prices.csv", "bitcoin_prices-2026-10-01.csv.gz", "bitcoin_prices-notes.txt", "keep.csv"] {
+            fs::write(simulation.join(name), "x\n").unwrap();
+        }
+        config.simulation.dir = simulation.to_string_lossy().into_owned();
+
+        assert_eq!(redirect(&mut config).unwrap(), simulation);
+        assert_eq!(config.assets[0].file, simulation.join("bitcoin_prices.csv").to_string_lossy());
+        let mut left: Vec<String> =
+            fs::read_dir(&simulation).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        left.sort();
+        assert_eq!(left, ["bitcoin_prices-notes.txt", "keep.csv"]);
+
+        // The directory holding the real files is refused
+        let mut config = Config::default();
+        config.assets[0].file = dir.join("bitcoin_prices.csv").to_string_lossy().into_owned();
+        config.simulation.dir = dir.to_string_lossy().into_owned();
+        assert!(redirect(&mut config).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
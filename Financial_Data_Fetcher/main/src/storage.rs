@@ -0,0 +1,671 @@
+// Price file storage: buffered appends, crash recovery, rotation, compression and retention
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc}; // For dated file names
+use flate2::read::GzDecoder;  // For reading compressed history
+use flate2::write::GzEncoder; // For compressing rotated files
+use flate2::Compression;
+use std::error::Error;   // For error handling
+use std::fs::{self, File, OpenOptions}; // For file operations
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write}; // For reading and writing records
+use std::path::{Path, PathBuf}; // For building file names
+use std::time::{Duration, Instant}; // For the flush interval
+
+use crate::config::{RetentionAction, Rotation, StorageConfig};
+use crate::log::warning;
+
+/// Seconds in a day, the unit of the retention policy
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// One saved price: a `timestamp,price,source` line
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceRecord {
+    pub timestamp: u64,  // Unix seconds
+    pub price: f64,      // USD
+    pub source: String,  // Provider(s) that produced the price
+    pub session: Option<String>, // Market session tag for scheduled assets ("regular", "pre", "post", "closed")
+}
+
+impl PriceRecord {
+    /// Parses a record line, None for anything malformed
+    pub fn parse(line: &str) -> Option<PriceRecord> {
+        let mut fields = line.trim().splitn(4, ',');
+        let timestamp = fields.next()?.parse().ok()?;
+        let price = fields.next()?.parse().ok()?;
+        let source = fields.next()?.to_string();
+        let session = fields.next().map(str::to_string);  // Absent in older and unscheduled files
+        Some(PriceRecord { timestamp, price, source, session })
+    }
+
+    /// Formats the record as a file line (without the newline)
+    pub fn to_line(&self) -> String {
+        match &self.session {
+            Some(session) => format!("{},{},{},{}", self.timestamp, self.price, self.source, session),
+            None => format!("{},{},{}", self.timestamp, self.price, self.source),
+        }
+    }
+}
+
+/// Append-only price history for one asset, split into rotated files.
+///
+/// The current file stays open and records are buffered in memory. A flush writes
+/// whole records only and (optionally) fsyncs, so a crash can at worst leave one
+/// partial line at the end of the file, which the writer's `recover` truncates on the next
+/// start. Readers skip such a line instead, it may be a record another process is writing.
+pub struct PriceStore {
+    dir: PathBuf,        // Directory holding the files
+    stem: String,        // "bitcoin_prices" for bitcoin_prices.csv
+    ext: String,         // "csv"
+    settings: StorageConfig,
+    active: Option<PathBuf>, // File currently being appended to
+    file: Option<File>,      // Open handle for `active`
+    buffer: Vec<u8>,         // Complete records not yet written
+    last_flush: Instant,
+}
+
+impl PriceStore {
+    /// Creates a store for the configured file name, e.g. "bitcoin_prices.csv". Nothing on
+    /// disk is touched until records are appended.
+    pub fn new(file: &str, settings: &StorageConfig) -> PriceStore {
+        let path = Path::new(file);
+        PriceStore {
+            dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            stem: path.file_stem().and_then(|s| s.to_str()).unwrap_or(file).to_string(),
+            ext: path.extension().and_then(|s| s.to_str()).unwrap_or("csv").to_string(),
+            settings: settings.clone(),
+            active: None,
+            file: None,
+            buffer: Vec::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Buffers one record line, rotating first if the policy says so
+    pub fn append(&mut self, timestamp: u64, line: &str) -> Result<(), Box<dyn Error>> {
+        let path = match self.settings.rotate {
+            Rotation::None | Rotation::Size(_) => self.base_path(),
+            Rotation::Daily => self.dated_path(&format_date(timestamp)),
+        };
+
+        // A new file means the old one is finished (first write since startup tidies leftovers)
+        let mut rotated = self.active.as_ref() != Some(&path);
+
+        // Size rotation moves the full file aside under a timestamped name and starts over
+        if let Rotation::Size(max_bytes) = self.settings.rotate {
+            let on_disk = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            if on_disk + self.buffer.len() as u64 >= max_bytes && on_disk > 0 {
+                self.close()?;
+                fs::rename(&path, self.free_rotated_path(&format_datetime(timestamp)))?;
+                rotated = true;
+            }
+        }
+
+        if rotated {
+            self.close()?;  // Finish the old file before it gets compressed
+            self.active = Some(path.clone());
+            self.maintain(timestamp);
+        }
+
+        self.buffer.extend_from_slice(line.as_bytes());
+        self.buffer.push(b'\n');
+        self.flush_if_due()
+    }
+
+    /// Writes buffered records once `flush_interval_secs` has passed since the last flush;
+    /// called on every append and regularly by the writer so quiet assets get written too
+    pub fn flush_if_due(&mut self) -> Result<(), Box<dyn Error>> {
+        let interval = Duration::from_secs(self.settings.flush_interval_secs);
+        if self.last_flush.elapsed() >= interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes buffered records to the current file and syncs it to disk
+    pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.last_flush = Instant::now();
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let Some(path) = &self.active else {
+            return Ok(());
+        };
+        if self.file.is_none() {
+            // Open file in append mode, create if doesn't exist
+            self.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        }
+        if let Some(file) = &mut self.file {
+            // The buffer is kept for the next try after a failure, so cut off whatever part of it
+            // got written: writing it again after that would duplicate records or tear a line
+            let len = file.metadata()?.len();
+            let written = file
+                .write_all(&self.buffer)
+                .and_then(|_| if self.settings.fsync { file.sync_data() } else { Ok(()) });
+            if let Err(e) = written {
+                if let Err(truncate) = file.set_len(len) {
+                    warning!("Error undoing a partial write to {}: {}", path.display(), truncate);
+                }
+                return Err(e.into());
+            }
+        }
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Writes records from elsewhere (e.g. an import), oldest first, straight into rotated files:
+    /// one per UTC day with daily rotation, otherwise one file named after the first record.
+    /// The live file isn't touched and no rotation or retention runs. Returns the files written.
+    pub fn write_archive(&self, records: &[PriceRecord]) -> io::Result<Vec<PathBuf>> {
+        let Some(first) = records.first() else {
+            return Ok(Vec::new());
+        };
+        let single = self.free_rotated_path(&format_datetime(first.timestamp));
+        let mut written: Vec<PathBuf> = Vec::new();
+        let mut file: Option<File> = None;
+        for record in records {
+            let path = match self.settings.rotate {
+                Rotation::Daily => self.dated_path(&format_date(record.timestamp)),
+                Rotation::None | Rotation::Size(_) => single.clone(),
+            };
+            if written.last() != Some(&path) {
+                if let Some(file) = file.take() {
+                    file.sync_all()?;
+                }
+                file = Some(OpenOptions::new().create(true).append(true).open(&path)?);
+                written.push(path);
+            }
+            if let Some(file) = &mut file {
+                writeln!(file, "{}", record.to_line())?;
+            }
+        }
+        if let Some(file) = file {
+            file.sync_all()?;
+        }
+        Ok(written)
+    }
+
+    /// Flushes and closes the current file
+    fn close(&mut self) -> Result<(), Box<dyn Error>> {
+        self.flush()?;
+        self.file = None;
+        Ok(())
+    }
+
+    /// Most recent saved record across the current and rotated files
+    pub fn last_record(&self) -> io::Result<Option<PriceRecord>> {
+        let mut newest: Option<PriceRecord> = None;
+
+        // The base file is current unless rotation is daily; rotated files newest first
+        let mut candidates = vec![self.base_path()];
+        let mut rotated = self.rotated_files();
+        rotated.reverse();
+        candidates.extend(rotated.into_iter().map(|(_, path)| path));
+
+        for path in candidates.iter().filter(|path| path.exists()) {
+            // Newest by time rather than position, imported history can follow newer records
+            let mut last_in_file: Option<PriceRecord> = None;
+            for line in complete_lines(path)? {
+                if let Some(record) = PriceRecord::parse(&line) {
+                    if last_in_file.as_ref().is_none_or(|best| record.timestamp >= best.timestamp) {
+                        last_in_file = Some(record);
+                    }
+                }
+            }
+            let is_rotated = *path != self.base_path();
+            if let Some(record) = last_in_file {
+                if newest.as_ref().is_none_or(|best| record.timestamp > best.timestamp) {
+                    newest = Some(record);
+                }
+                // Older rotated files can't beat the newest one that has records
+                if is_rotated {
+                    break;
+                }
+            }
+        }
+        Ok(newest)
+    }
+
+    /// Every saved record across the rotated and current files, oldest first.
+    /// Malformed lines and an unfinished last line are skipped.
+    pub fn history(&self) -> io::Result<Vec<PriceRecord>> {
+        let mut candidates: Vec<PathBuf> = self.rotated_files().into_iter().map(|(_, path)| path).collect();
+        candidates.push(self.base_path());
+
+        let mut records = Vec::new();
+        for path in candidates.iter().filter(|path| path.exists()) {
+            for line in complete_lines(path)? {
+                records.extend(PriceRecord::parse(&line));
+            }
+        }
+        // The base file can predate a switch to daily rotation, so order by time rather than by file
+        records.sort_by_key(|record| record.timestamp);
+        Ok(records)
+    }
+
+    /// Truncates partial trailing lines left by a crash in every uncompressed file of the series.
+    /// Only the process that writes the series may call this, once before its first append.
+    pub fn recover(&self) {
+        let mut candidates = vec![self.base_path()];
+        candidates.extend(self.rotated_files().into_iter().map(|(_, path)| path));
+        for path in candidates.iter().filter(|path| !is_gzip(path) && path.exists()) {
+            match truncate_partial_line(path) {
+                Ok(0) => {}
+                Ok(removed) => warning!("Recovered {}: dropped {} bytes of a torn record", path.display(), removed),
+                Err(e) => warning!("Error recovering {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// "bitcoin_prices.csv"
+    fn base_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.stem, self.ext))
+    }
+
+    /// "bitcoin_prices-2026-10-18.csv"
+    fn dated_path(&self, suffix: &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.{}", self.stem, suffix, self.ext))
+    }
+
+    /// `dated_path(suffix)`, or with "-1", "-2"... added if that name (or its .gz) is taken,
+    /// so two size rotations in the same second don't overwrite each other
+    fn free_rotated_path(&self, suffix: &str) -> PathBuf {
+        let taken = |path: &PathBuf| {
+            let mut compressed = path.as_os_str().to_owned();
+            compressed.push(".gz");
+            path.exists() || Path::new(&compressed).exists()
+        };
+        let mut path = self.dated_path(suffix);
+        let mut counter = 1;
+        while taken(&path) {
+            path = self.dated_path(&format!("{}-{}", suffix, counter));
+            counter += 1;
+        }
+        path
+    }
+
+    /// Every rotated file of this series with the date it covers, oldest first. Files are
+    /// ordered by the time and counter in their names, not by the names as text.
+    pub fn rotated_files(&self) -> Vec<(NaiveDate, PathBuf)> {
+        let dir = if self.dir.as_os_str().is_empty() { Path::new(".") } else { &self.dir };
+        let mut files = Vec::new();
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(order) = self.rotated_order(&name) {
+                    files.push((order, self.dir.join(&name)));
+                }
+            }
+        }
+        files.sort();
+        files.into_iter().map(|((date, ..), path)| (date, path)).collect()
+    }
+
+    /// The live file, if there is one, and every rotated file of this series
+    pub fn files(&self) -> Vec<PathBuf> {
+        let base = self.base_path();
+        base.exists()
+            .then_some(base)
+            .into_iter()
+            .chain(self.rotated_files().into_iter().map(|(_, path)| path))
+            .collect()
+    }
+
+    /// Date, time and counter of a rotated file name this store could have written:
+    /// "stem-2026-10-18.csv", "stem-2026-10-18T14-05-00.csv" or "stem-2026-10-18T14-05-00-1.csv",
+    /// each maybe with ".gz". None for any other name.
+    fn rotated_order(&self, name: &str) -> Option<(NaiveDate, NaiveTime, u32)> {
+        let name = name.strip_suffix(".gz").unwrap_or(name);
+        let rest = name
+            .strip_prefix(&format!("{}-", self.stem))?
+            .strip_suffix(&format!(".{}", self.ext))?;
+        let (date, time) = rest.split_at_checked(10)?;
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+        if time.is_empty() {
+            return Some((date, NaiveTime::MIN, 0));  // Daily
+        }
+        // Size rotation: a time of day, then a counter if that second was taken
+        let (time, counter) = time.strip_prefix('T')?.split_at_checked(8)?;
+        let time = NaiveTime::parse_from_str(time, "%H-%M-%S").ok()?;
+        let counter = match counter.strip_prefix('-') {
+            None if counter.is_empty() => 0,
+            Some(digits) if !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) => digits.parse().ok()?,
+            _ => return None,
+        };
+        Some((date, time, counter))
+    }
+
+    /// Compresses finished files and enforces retention. Problems are logged, not fatal.
+    fn maintain(&self, now: u64) {
+        for (date, mut path) in self.rotated_files() {
+            if Some(&path) == self.active.as_ref() {
+                continue;  // Still being written
+            }
+            let expired = self
+                .settings
+                .retention
+                .as_ref()
+                .filter(|retention| now.saturating_sub(date_to_unix(date)) / DAY_SECS >= retention.days);
+
+            // No point compressing a file that is about to be deleted
+            if let Some(retention) = expired {
+                if retention.action == RetentionAction::Delete {
+                    if let Err(e) = fs::remove_file(&path) {
+                        warning!("Error deleting {}: {}", path.display(), e);
+                    }
+                    continue;
+                }
+            }
+
+            if self.settings.compress && !is_gzip(&path) {
+                match compress(&path) {
+                    Ok(compressed) => path = compressed,
+                    Err(e) => warning!("Error compressing {}: {}", path.display(), e),
+                }
+            }
+
+            if let Some(retention) = expired {
+                if let Err(e) = downsample(&path, retention.downsample_secs) {
+                    warning!("Error downsampling {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PriceStore {
+    /// Last chance to write buffered records (normal exit or shutdown)
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            warning!("Error flushing {}.{}: {}", self.stem, self.ext, e);
+        }
+    }
+}
+
+/// Cuts a file back to its last newline, returning how many bytes were removed
+fn truncate_partial_line(path: &Path) -> io::Result<u64> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let len = file.metadata()?.len();
+
+    // Walk backwards in chunks until a newline (or the start of the file) is found
+    let mut end = len;
+    let mut chunk = [0u8; 4096];
+    while end > 0 {
+        let start = end.saturating_sub(chunk.len() as u64);
+        let size = (end - start) as usize;
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk[..size])?;
+        if let Some(newline) = chunk[..size].iter().rposition(|byte| *byte == b'\n') {
+            end = start + newline as u64 + 1;
+            break;
+        }
+        end = start;
+    }
+
+    if end < len {
+        file.set_len(end)?;
+        file.sync_all()?;
+    }
+    Ok(len - end)
+}
+
+/// Complete lines of a history file. A last line without its newline is either torn by a crash
+/// or still being written by another process, and is left out.
+fn complete_lines(path: &Path) -> io::Result<Vec<String>> {
+    let mut contents = String::new();
+    open_records(path)?.read_to_string(&mut contents)?;
+    let mut lines: Vec<String> = contents.split('\n').map(|line| line.trim_end_matches('\r').to_string()).collect();
+    lines.pop();  // Empty after a final newline, unfinished otherwise
+    Ok(lines)
+}
+
+/// Opens a history file for reading, transparently decompressing .gz files
+pub fn open_records(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    if is_gzip(path) {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// True for "*.gz" files
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Gzips `path` to `path.gz` and removes the original
+fn compress(path: &Path) -> io::Result<PathBuf> {
+    let mut compressed = path.as_os_str().to_owned();
+    compressed.push(".gz");
+    let compressed = PathBuf::from(compressed);
+
+    let mut input = Vec::new();
+    File::open(path)?.read_to_end(&mut input)?;
+    let mut encoder = GzEncoder::new(File::create(&compressed)?, Compression::default());
+    encoder.write_all(&input)?;
+    encoder.finish()?;
+    fs::remove_file(path)?;
+    Ok(compressed)
+}
+
+/// Keeps only the first record in every `bucket_secs` window, rewriting the file if anything was dropped
+fn downsample(path: &Path, bucket_secs: u64) -> Result<(), Box<dyn Error>> {
+    let mut kept = Vec::new();
+    let mut dropped = 0;
+    let mut last_bucket = None;
+    for line in open_records(path)?.lines() {
+        let line = line?;
+        let bucket = PriceRecord::parse(&line).map(|record| record.timestamp / bucket_secs.max(1));
+        // Unparseable lines are kept untouched
+        if bucket.is_some() && bucket == last_bucket {
+            dropped += 1;
+            continue;
+        }
+        last_bucket = bucket.or(last_bucket);
+        kept.push(line);
+    }
+    if dropped == 0 {
+        return Ok(());
+    }
+
+    // Write next to the original and rename over it, so a crash never loses the file
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    let mut contents = kept.join("\n");
+    contents.push('\n');
+    let file = File::create(&temp)?;
+    if is_gzip(path) {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(contents.as_bytes())?;
+        encoder.finish()?.sync_all()?;
+    } else {
+        let mut file = file;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// "2026-10-18" for a unix timestamp (UTC)
+fn format_date(timestamp: u64) -> String {
+    to_datetime(timestamp).format("%Y-%m-%d").to_string()
+}
+
+/// "2026-10-18T14-05-00" for a unix timestamp (UTC), safe in file names
+fn format_datetime(timestamp: u64) -> String {
+    to_datetime(timestamp).format("%Y-%m-%dT%H-%M-%S").to_string()
+}
+
+fn to_datetime(timestamp: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default()
+}
+
+/// Midnight UTC of `date` as a unix timestamp
+fn date_to_unix(date: NaiveDate) -> u64 {
+    date.and_hms_opt(0, 0, 0)
+        .map(|midnight| midnight.and_utc().timestamp().max(0) as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RetentionConfig;
+    use std::{env, process};
+
+    /// 2026-10-01 00:00:00 UTC
+    fn day_one() -> u64 {
+        date_to_unix(NaiveDate::from_ymd_opt(2026, 10, 1).unwrap())
+    }
+
+    /// An empty directory of the test's own under the system temp dir
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("fdf-storage-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn settings(rotate: Rotation) -> StorageConfig {
+        StorageConfig { rotate, fsync: false, ..StorageConfig::default() }
+    }
+
+    fn store(dir: &Path, settings: &StorageConfig) -> PriceStore {
+        PriceStore::new(dir.join("btc.csv").to_str().unwrap(), settings)
+    }
+
+    fn append(store: &mut PriceStore, timestamp: u64, price: f64) {
+        let record = PriceRecord { timestamp, price, source: "test".to_string(), session: None };
+        store.append(timestamp, &record.to_line()).unwrap();
+    }
+
+    fn names(store: &PriceStore) -> Vec<String> {
+        store
+            .rotated_files()
+            .iter()
+            .map(|(_, path)| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn prices(path: &Path) -> Vec<f64> {
+        complete_lines(path).unwrap().iter().filter_map(|line| PriceRecord::parse(line)).map(|record| record.price).collect()
+    }
+
+    #[test]
+    fn size_rotations_in_one_second_are_numbered_and_kept_in_order() {
+        let dir = temp_dir("size");
+        let settings = StorageConfig { compress: true, ..settings(Rotation::Size(1024)) };
+        let mut store = store(&dir, &settings);
+        let timestamp = day_one() + 3600;
+        for price in 1000..1600 {
+            append(&mut store, timestamp, price as f64);
+        }
+        store.flush().unwrap();
+
+        // Counters follow the unnumbered file, and -10 comes after -9
+        let names = names(&store);
+        assert!(names.len() > 10, "only {} rotations", names.len());
+        assert_eq!(names[0], "btc-2026-10-01T01-00-00.csv.gz");
+        for (counter, name) in names.iter().enumerate().skip(1) {
+            assert_eq!(*name, format!("btc-2026-10-01T01-00-00-{}.csv.gz", counter));
+        }
+
+        // Reading the files in that order gives the records back in the order they were written
+        let mut all: Vec<f64> = store.rotated_files().iter().flat_map(|(_, path)| prices(path)).collect();
+        all.extend(prices(&store.base_path()));
+        assert_eq!(all, (1000..1600).map(|price| price as f64).collect::<Vec<_>>());
+        assert_eq!(store.last_record().unwrap().unwrap().price, 1599.0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn daily_rotation_starts_a_file_per_day() {
+        let dir = temp_dir("daily");
+        let mut store = store(&dir, &settings(Rotation::Daily));
+        append(&mut store, day_one() + 10, 1.0);
+        append(&mut store, day_one() + 20, 2.0);
+        append(&mut store, day_one() + DAY_SECS + 5, 3.0);
+        store.flush().unwrap();
+
+        assert_eq!(names(&store), ["btc-2026-10-01.csv", "btc-2026-10-02.csv"]);
+        assert!(!store.base_path().exists());
+        let history: Vec<f64> = store.history().unwrap().iter().map(|record| record.price).collect();
+        assert_eq!(history, [1.0, 2.0, 3.0]);
+        assert_eq!(store.last_record().unwrap().unwrap().timestamp, day_one() + DAY_SECS + 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn retention_deletes_expired_files() {
+        let dir = temp_dir("delete");
+        let retention = RetentionConfig { days: 3, action: RetentionAction::Delete, downsample_secs: 3600 };
+        let settings = StorageConfig { retention: Some(retention), ..settings(Rotation::Daily) };
+        let mut store = store(&dir, &settings);
+        for day in [0, 1, 3, 5] {
+            append(&mut store, day_one() + day * DAY_SECS, day as f64);
+        }
+        store.flush().unwrap();
+
+        // Retention counts from the newest record: on day 5, days 0 and 1 are 3 or more days old
+        assert_eq!(names(&store), ["btc-2026-10-04.csv", "btc-2026-10-06.csv"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn retention_downsamples_expired_files() {
+        let dir = temp_dir("downsample");
+        let retention = RetentionConfig { days: 1, action: RetentionAction::Downsample, downsample_secs: 3600 };
+        let settings = StorageConfig { compress: true, retention: Some(retention), ..settings(Rotation::Daily) };
+        let mut store = store(&dir, &settings);
+        for step in 0..12 {
+            append(&mut store, day_one() + step * 600, step as f64);
+        }
+        append(&mut store, day_one() + 3 * DAY_SECS, 99.0);
+        store.flush().unwrap();
+
+        // Two hours of ten-minute records become one per hour, compressed like any finished file
+        assert_eq!(names(&store), ["btc-2026-10-01.csv.gz", "btc-2026-10-04.csv"]);
+        assert_eq!(prices(&dir.join("btc-2026-10-01.csv.gz")), [0.0, 6.0]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn torn_last_line_is_skipped_by_readers_and_cut_by_recover() {
+        let dir = temp_dir("torn");
+        let store = store(&dir, &settings(Rotation::None));
+        fs::write(store.base_path(), "100,1.5,test\n200,2.5,test\n300,3.").unwrap();
+
+        // Readers leave the unfinished line alone, it may still be being written
+        assert_eq!(store.last_record().unwrap().unwrap().timestamp, 200);
+        assert_eq!(store.history().unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(store.base_path()).unwrap().len(), 32);
+
+        // The writer cuts it before appending after it
+        store.recover();
+        assert_eq!(fs::read_to_string(store.base_path()).unwrap(), "100,1.5,test\n200,2.5,test\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn records_are_buffered_until_the_flush_interval() {
+        let dir = temp_dir("buffered");
+        let settings = StorageConfig { flush_interval_secs: 3600, ..settings(Rotation::None) };
+        let mut store = store(&dir, &settings);
+        append(&mut store, day_one(), 1.0);
+        append(&mut store, day_one() + 1, 2.0);
+
+        // Nothing is due for an hour, so the records are still in memory
+        store.flush_if_due().unwrap();
+        assert_eq!(fs::read_to_string(store.base_path()).unwrap_or_default(), "");
+
+        // A flush writes them whole and in order, and leaves nothing to write twice
+        store.flush().unwrap();
+        store.flush().unwrap();
+        assert_eq!(prices(&store.base_path()), [1.0, 2.0]);
+
+        // Dropping the store (shutdown) writes what is left
+        append(&mut store, day_one() + 2, 3.0);
+        drop(store);
+        assert_eq!(prices(&dir.join("btc.csv")), [1.0, 2.0, 3.0]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,449 @@
+// Streaming price feeds: websocket ticker channels pushed into the normal asset pipeline
+use serde::Deserialize;  // For JSON deserialization
+use std::error::Error;   // For error handling
+use std::io;             // For the stand-in server's errors
+use std::net::{TcpListener, TcpStream}; // For sockets and read timeouts
+use std::sync::mpsc::{self, Receiver, Sender}; // For handing ticks to the asset
+use std::thread;         // One thread per feed
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH}; // For backoff, staleness, pacing and seeding
+
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::asset::unix_now;
+use crate::config::StreamConfig;
+use crate::log::warning;
+use crate::shutdown;
+use crate::validate;
+
+/// First reconnect delay, doubled after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between reconnect attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Reconnect if the feed goes quiet for this long (exchanges send several ticks a minute)
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// How often a blocked read wakes up to check for shutdown and staleness
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// One price pushed by a feed
+#[derive(Debug, Clone, Copy)]
+pub struct Tick {
+    pub price: f64,      // USD
+    pub timestamp: u64,  // Unix time the tick arrived
+}
+
+/// What the feed thread reports: a tick, or why the connection dropped
+pub type StreamEvent = Result<Tick, String>;
+
+/// A websocket ticker channel for one symbol
+pub trait StreamSource: Send {
+    /// Name recorded as the source of streamed prices (e.g. "coinbase-ws")
+    fn name(&self) -> &str;
+
+    /// Public endpoint of the feed
+    fn url(&self) -> String;
+
+    /// Message sent after connecting, if the feed needs an explicit subscription
+    fn subscribe(&self) -> Option<String>;
+
+    /// Extracts the USD price from a ticker message; Ok(None) for other message types
+    fn parse(&self, message: &str) -> Result<Option<f64>, Box<dyn Error>>;
+}
+
+/// Builds a feed from its config entry, rejecting providers without a streaming API
+pub fn build(config: &StreamConfig) -> Result<Box<dyn StreamSource>, Box<dyn Error>> {
+    let symbol = config.symbol.to_uppercase();
+    match config.provider.as_str() {
+        "coinbase" => Ok(Box::new(CoinbaseStream { symbol })),
+        "binance" => Ok(Box::new(BinanceStream { symbol })),
+        other => Err(format!("no streaming feed for provider '{}'", other).into()),
+    }
+}
+
+/// Connects on a background thread and keeps reconnecting until shutdown.
+/// `url` overrides the source's endpoint (e.g. a local stand-in).
+pub fn spawn(asset: &str, source: Box<dyn StreamSource>, url: Option<String>) -> Receiver<StreamEvent> {
+    let (tx, rx) = mpsc::channel();
+    let asset = asset.to_string();
+    let url = url.unwrap_or_else(|| source.url());
+    thread::spawn(move || run(&asset, source.as_ref(), &url, &tx));
+    rx
+}
+
+/// Reconnect loop with exponential backoff, reset whenever a connection delivers ticks
+fn run(asset: &str, source: &dyn StreamSource, url: &str, tx: &Sender<StreamEvent>) {
+    let mut backoff = INITIAL_BACKOFF;
+    while !shutdown::requested() {
+        let mut delivered = false;
+        let reason = match read_feed(asset, source, url, tx, &mut delivered) {
+            Ok(()) => break,  // Shutdown or the asset went away
+            Err(e) => e.to_string(),
+        };
+        if delivered {
+            backoff = INITIAL_BACKOFF;
+        }
+        warning!("{}: {} stream dropped ({}), reconnecting in {}s", asset, source.name(), reason, backoff.as_secs());
+        if tx.send(Err(reason)).is_err() || !shutdown::sleep(backoff) {
+            break;
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// One connection: subscribe, then forward ticks until the socket fails or goes stale.
+/// Returns Ok only when it's time to stop.
+fn read_feed(
+    asset: &str,
+    source: &dyn StreamSource,
+    url: &str,
+    tx: &Sender<StreamEvent>,
+    delivered: &mut bool,
+) -> Result<(), Box<dyn Error>> {
+    let (mut socket, _) = tungstenite::connect(url)?;
+    set_read_timeout(&socket)?;
+    if let Some(subscribe) = source.subscribe() {
+        socket.send(Message::Text(subscribe))?;
+    }
+
+    let mut last_message = Instant::now();
+    while !shutdown::requested() {
+        let message = match socket.read() {
+            Ok(message) => message,
+            // Read timeout: nothing arrived this second
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) =>
+            {
+                if last_message.elapsed() > STALE_AFTER {
+                    return Err(format!("no data for {}s", STALE_AFTER.as_secs()).into());
+                }
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        last_message = Instant::now();
+
+        match message {
+            Message::Text(text) => {
+                // A message that doesn't parse ends the connection like a disconnect would
+                let parsed = source
+                    .parse(&text)
+                    .and_then(|price| price.map(|price| validate::check_price(source.name(), price)).transpose());
+                if parsed.is_err() {
+                    if let Some(path) = validate::quarantine(source.name(), asset, &text) {
+                        warning!("{}: saved the rejected {} message to {}", asset, source.name(), path.display());
+                    }
+                }
+                if let Some(price) = parsed? {
+                    let tick = Tick { price, timestamp: unix_now() };
+                    if tx.send(Ok(tick)).is_err() {
+                        return Ok(());  // Nobody is listening any more
+                    }
+                    *delivered = true;
+                }
+            }
+            Message::Close(_) => return Err("closed by server".into()),
+            _ => {}  // Pings are answered by tungstenite
+        }
+    }
+    let _ = socket.close(None);
+    Ok(())
+}
+
+/// Lets the read loop wake up regularly instead of blocking forever
+fn set_read_timeout(socket: &WebSocket<MaybeTlsStream<TcpStream>>) -> io::Result<()> {
+    match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(READ_TIMEOUT)),
+        MaybeTlsStream::Rustls(stream) => stream.get_ref().set_read_timeout(Some(READ_TIMEOUT)),
+        _ => Ok(()),
+    }
+}
+
+/// Coinbase Exchange ticker channel (`symbol` is the base currency like "BTC")
+pub struct CoinbaseStream {
+    symbol: String,
+}
+
+impl CoinbaseStream {
+    fn product_id(&self) -> String {
+        format!("{}-USD", self.symbol)
+    }
+}
+
+impl StreamSource for CoinbaseStream {
+    fn name(&self) -> &str {
+        "coinbase-ws"
+    }
+
+    fn url(&self) -> String {
+        "wss://ws-feed.exchange.coinbase.com".to_string()
+    }
+
+    fn subscribe(&self) -> Option<String> {
+        Some(
+            serde_json::json!({
+                "type": "subscribe",
+                "product_ids": [self.product_id()],
+                "channels": ["ticker"],
+            })
+            .to_string(),
+        )
+    }
+
+    fn parse(&self, message: &str) -> Result<Option<f64>, Box<dyn Error>> {
+        // {"type": "ticker", "product_id": "BTC-USD", "price": "84612.34", ...}
+        // plus "subscriptions" acknowledgements and {"type": "error", "message": ...}
+        #[derive(Deserialize)]
+        struct Event {
+            #[serde(rename = "type")]
+            kind: String,
+            product_id: Option<String>,
+            price: Option<String>,
+            message: Option<String>,
+        }
+
+        let event: Event = validate::from_json(self.name(), message)?;
+        match event.kind.as_str() {
+            "ticker" if event.product_id.as_deref() == Some(self.product_id().as_str()) => {
+                let price = event.price.ok_or("coinbase ticker without a price")?;
+                Ok(Some(validate::number(self.name(), "price", &price)?))
+            }
+            "error" => Err(format!("coinbase: {}", event.message.unwrap_or_default()).into()),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Binance individual symbol ticker stream (`symbol` like "BTC", quoted in USDT)
+pub struct BinanceStream {
+    symbol: String,
+}
+
+impl BinanceStream {
+    fn pair(&self) -> String {
+        format!("{}USDT", self.symbol)
+    }
+}
+
+impl StreamSource for BinanceStream {
+    fn name(&self) -> &str {
+        "binance-ws"
+    }
+
+    fn url(&self) -> String {
+        // The stream is chosen by path, no subscribe message needed
+        format!("wss://stream.binance.com:9443/ws/{}@ticker", self.pair().to_lowercase())
+    }
+
+    fn subscribe(&self) -> Option<String> {
+        None
+    }
+
+    fn parse(&self, message: &str) -> Result<Option<f64>, Box<dyn Error>> {
+        // {"e": "24hrTicker", "s": "BTCUSDT", "c": "84618.01", ...} where "c" is the last price
+        #[derive(Deserialize)]
+        struct Event {
+            e: Option<String>,
+            s: Option<String>,
+            c: Option<String>,
+        }
+
+        let event: Event = validate::from_json(self.name(), message)?;
+        if event.e.as_deref() != Some("24hrTicker") {
+            return Ok(None);
+        }
+        if event.s.as_deref() != Some(self.pair().as_str()) {
+            return Err(format!("binance ticker for {:?}, expected {}", event.s, self.pair()).into());
+        }
+        let price = event.c.ok_or("binance ticker without a last price")?;
+        Ok(Some(validate::number(self.name(), "c", &price)?))
+    }
+}
+
+/// Default address of the local stand-in feed
+pub const STANDIN_ADDR: &str = "127.0.0.1:9001";
+
+/// Ticks the stand-in sends before hanging up, so clients exercise their reconnect path
+const STANDIN_TICKS_PER_CONNECTION: u32 = 60;
+
+/// Pause between the stand-in's ticks
+const STANDIN_TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How the stand-in paces each client
+#[derive(Debug, Clone, Copy)]
+struct Pacing {
+    ticks_per_connection: u32,
+    interval: Duration,
+}
+
+/// Local websocket server imitating both exchange feeds with a random walk, for trying out
+/// streaming without network access. Binance clients are recognised by their `/ws/<pair>@ticker`
+/// path, anyone else gets Coinbase-style tickers for the product they subscribe to.
+pub fn serve_standin(addr: &str, start_price: f64) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("Stand-in feed on ws://{} (Binance clients use /ws/btcusdt@ticker)", addr);
+    let pacing = Pacing {
+        ticks_per_connection: STANDIN_TICKS_PER_CONNECTION,
+        interval: STANDIN_TICK_INTERVAL,
+    };
+    serve_standin_on(listener, start_price, pacing)
+}
+
+/// Accepts stand-in clients on an already bound listener
+fn serve_standin_on(listener: TcpListener, start_price: f64, pacing: Pacing) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(e) = serve_client(stream, start_price, pacing) {
+                println!("Stand-in client ended: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Sends one client ticks until it disconnects or the tick budget runs out
+fn serve_client(stream: TcpStream, start_price: f64, pacing: Pacing) -> Result<(), Box<dyn Error>> {
+    let peer = stream.peer_addr()?;
+    let (path_tx, path_rx) = mpsc::channel();
+    #[allow(clippy::result_large_err)] // The handshake callback's signature is fixed by tungstenite
+    let mut socket = tungstenite::accept_hdr(
+        stream,
+        move |request: &tungstenite::handshake::server::Request, response| {
+            let _ = path_tx.send(request.uri().path().to_string());
+            Ok(response)
+        },
+    )?;
+    let path = path_rx.try_recv().unwrap_or_default();
+
+    // Binance picks the pair from the path, Coinbase clients subscribe first
+    let binance_pair = path
+        .strip_prefix("/ws/")
+        .and_then(|rest| rest.strip_suffix("@ticker"))
+        .map(str::to_uppercase);
+    let coinbase_product = match binance_pair {
+        Some(_) => None,
+        None => {
+            let subscribe: serde_json::Value = match socket.read()? {
+                Message::Text(text) => serde_json::from_str(&text)?,
+                other => return Err(format!("expected a subscribe message, got {:?}", other).into()),
+            };
+            let product = subscribe["product_ids"][0].as_str().unwrap_or("BTC-USD").to_string();
+            socket.send(Message::Text(
+                serde_json::json!({"type": "subscriptions", "channels": [{"name": "ticker", "product_ids": [product]}]})
+                    .to_string(),
+            ))?;
+            Some(product)
+        }
+    };
+    println!("Stand-in client {} connected ({})", peer, binance_pair.as_deref().or(coinbase_product.as_deref()).unwrap_or("?"));
+
+    let mut walk = RandomWalk::new(start_price);
+    for _ in 0..pacing.ticks_per_connection {
+        let price = walk.step();
+        let message = match (&binance_pair, &coinbase_product) {
+            (Some(pair), _) => serde_json::json!({"e": "24hrTicker", "s": pair, "c": format!("{:.2}", price)}),
+            (None, product) => serde_json::json!({"type": "ticker", "product_id": product, "price": format!("{:.2}", price)}),
+        };
+        socket.send(Message::Text(message.to_string()))?;
+        thread::sleep(pacing.interval);
+    }
+    socket.close(None)?;
+    println!("Stand-in client {} dropped after {} ticks", peer, pacing.ticks_per_connection);
+    Ok(())
+}
+
+/// Small dependency-free random walk (xorshift noise, up to ±max_step per step)
+pub(crate) struct RandomWalk {
+    price: f64,
+    state: u64,
+    max_step: f64,  // Largest relative move per step, 0.001 = 0.1%
+}
+
+impl RandomWalk {
+    /// A walk seeded from the clock, moving up to 0.1% per step
+    fn new(price: f64) -> RandomWalk {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0x9e37_79b9);
+        RandomWalk::seeded(price, seed, 0.1)
+    }
+
+    /// A walk that repeats exactly for the same seed, moving up to `max_step_pct` percent per step
+    pub(crate) fn seeded(price: f64, seed: u64, max_step_pct: f64) -> RandomWalk {
+        RandomWalk { price, state: seed | 1, max_step: max_step_pct / 100.0 }
+    }
+
+    /// The price the walk is at, its start until the first step
+    pub(crate) fn price(&self) -> f64 {
+        self.price
+    }
+
+    pub(crate) fn step(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let noise = (self.state % 2001) as f64 / 1000.0 - 1.0;  // -1.0..=1.0
+        self.price *= 1.0 + noise * self.max_step;
+        self.price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Longest a test waits for the next feed event
+    const EVENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Starts a stand-in on an ephemeral port and returns its ws:// address
+    fn start_standin(ticks_per_connection: u32) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind stand-in");
+        let addr = listener.local_addr().expect("stand-in address");
+        let pacing = Pacing { ticks_per_connection, interval: Duration::from_millis(20) };
+        thread::spawn(move || serve_standin_on(listener, 84000.0, pacing));
+        format!("ws://{}", addr)
+    }
+
+    fn feed(provider: &str, url: String) -> Receiver<StreamEvent> {
+        let config = StreamConfig { provider: provider.to_string(), symbol: "BTC".to_string(), url: None };
+        spawn("Bitcoin", build(&config).expect("stream source"), Some(url))
+    }
+
+    /// Waits for `count` ticks, failing on a dropped connection
+    fn expect_ticks(events: &Receiver<StreamEvent>, count: usize) {
+        for _ in 0..count {
+            match events.recv_timeout(EVENT_TIMEOUT).expect("no event from the feed") {
+                Ok(tick) => assert!((80000.0..88000.0).contains(&tick.price), "implausible tick {}", tick.price),
+                Err(e) => panic!("feed dropped: {}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn coinbase_feed_parses_standin_ticks() {
+        let events = feed("coinbase", start_standin(100));
+        expect_ticks(&events, 3);
+    }
+
+    #[test]
+    fn binance_feed_parses_standin_ticks() {
+        let events = feed("binance", format!("{}/ws/btcusdt@ticker", start_standin(100)));
+        expect_ticks(&events, 3);
+    }
+
+    #[test]
+    fn dropped_feed_reconnects() {
+        let events = feed("coinbase", start_standin(3));
+        expect_ticks(&events, 3);
+        match events.recv_timeout(EVENT_TIMEOUT).expect("no event after the last tick") {
+            Err(_) => {}  // Dropped as expected
+            Ok(tick) => panic!("tick {} after the stand-in hung up", tick.price),
+        }
+        // The next connection, after INITIAL_BACKOFF, delivers again
+        expect_ticks(&events, 3);
+    }
+}
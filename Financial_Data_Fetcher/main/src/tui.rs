@@ -0,0 +1,268 @@
+// Full-screen terminal dashboard, refreshed in place while a background thread fetches
+use std::collections::VecDeque; // For the rolling price history
+use std::io;             // For terminal errors
+use std::sync::mpsc;     // For passing snapshots from the fetch thread
+use std::thread;         // For the background fetch loop
+use std::time::Duration; // For redraw timing
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use ratatui::Frame;
+
+use financial_data_fetcher::asset::{unix_now, Pricing, SourceStatus};
+use financial_data_fetcher::cache;
+use financial_data_fetcher::fetcher::Scheduler;
+use financial_data_fetcher::fx::Fx;
+use financial_data_fetcher::log::{self, warning};
+use financial_data_fetcher::portfolio::Portfolio;
+use financial_data_fetcher::shutdown;
+
+/// Warnings go here while the dashboard owns the terminal
+pub const LOG_PATH: &str = "fetcher.log";
+
+/// Number of prices kept for the sparkline
+const HISTORY_LEN: usize = 40;
+
+/// How often the screen is redrawn (also the key polling interval)
+const TICK: Duration = Duration::from_millis(250);
+
+/// Bar characters from lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// What the fetch thread reports after each asset
+struct Snapshot {
+    name: String,
+    price: Option<f64>,
+    price_text: String,       // Already converted to the display currency
+    updated: Option<u64>,     // Unix time of the asset's last price
+    error: Option<String>,    // Set if this fetch failed
+    sources: Vec<(String, SourceStatus)>,
+}
+
+/// One table row with its accumulated history
+struct AssetRow {
+    name: String,
+    price_text: String,
+    history: VecDeque<f64>,
+    updated: Option<u64>,      // Unix time of the last price (may predate this run)
+    error: Option<String>,
+    sources: Vec<(String, SourceStatus)>,
+}
+
+/// Runs the dashboard until the user presses q or Esc
+pub fn run(
+    mut assets: Vec<Box<dyn Pricing>>,
+    mut scheduler: Scheduler,
+    mut fx: Fx,
+    mut portfolio: Option<Portfolio>,
+) -> io::Result<()> {
+    log::to_file(LOG_PATH)?;
+
+    // Prices resumed from storage show up straight away
+    let mut rows: Vec<AssetRow> = assets
+        .iter()
+        .map(|asset| AssetRow {
+            name: asset.name().to_string(),
+            price_text: match asset.last_price() {
+                Some(price) => fx.format_usd(price),
+                None => "-".to_string(),
+            },
+            history: asset.last_price().into_iter().collect(),
+            updated: asset.last_updated(),
+            error: None,
+            sources: Vec::new(),
+        })
+        .collect();
+
+    // Fetching blocks for seconds at a time, so it runs on its own thread
+    let (tx, rx) = mpsc::channel::<Snapshot>();
+    let fetch_thread = thread::spawn(move || {
+        while !shutdown::requested() {
+            let fetched = scheduler.run_due(&mut assets, |asset, result| {
+                let snapshot = Snapshot {
+                    name: asset.name().to_string(),
+                    price: result.as_ref().ok().copied(),
+                    price_text: fx.format_usd(asset.last_price().unwrap_or(0.0)),
+                    updated: asset.last_updated(),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                    sources: asset
+                        .sources()
+                        .into_iter()
+                        .map(|(name, status)| (name.to_string(), status))
+                        .collect(),
+                };
+                let _ = tx.send(snapshot);  // UI gone, we're shutting down anyway
+            });
+            if let Some(portfolio) = &mut portfolio {
+                if fetched > 0 {
+                    if let Err(e) = portfolio.update(&assets, &mut fx) {
+                        warning!("Error updating portfolio: {}", e);
+                    }
+                }
+            }
+            scheduler.wait(|| {});
+            // The dashboard doesn't reload; drop a SIGHUP or every later wait would end at once
+            shutdown::take_reload();
+        }
+        scheduler.flush();
+    });
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> io::Result<()> {
+        while !shutdown::requested() {
+            // Fold in everything fetched since the last frame
+            while let Ok(snapshot) = rx.try_recv() {
+                apply(&mut rows, snapshot);
+            }
+            terminal.draw(|frame| draw(frame, &rows))?;
+
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+
+    // Let the fetch thread finish its current request and flush the price files
+    shutdown::request();
+    println!("Shutting down, flushing price files...");
+    let _ = fetch_thread.join();
+    result
+}
+
+/// Updates the matching row from a snapshot
+fn apply(rows: &mut [AssetRow], snapshot: Snapshot) {
+    let Some(row) = rows.iter_mut().find(|row| row.name == snapshot.name) else {
+        return;
+    };
+    if let Some(price) = snapshot.price {
+        if row.history.len() == HISTORY_LEN {
+            row.history.pop_front();
+        }
+        row.history.push_back(price);
+        row.updated = snapshot.updated;
+        row.price_text = snapshot.price_text;
+    }
+    row.error = snapshot.error;
+    row.sources = snapshot.sources;
+}
+
+/// Draws the asset table and a footer
+fn draw(frame: &mut Frame, rows: &[AssetRow]) {
+    let [table_area, footer_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+
+    let header = Row::new(["Asset", "Price", "Change", "Trend", "Updated", "Sources"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let table_rows = rows.iter().map(|row| {
+        let (change_text, change_color) = match (row.history.front(), row.history.back()) {
+            (Some(first), Some(last)) if row.history.len() > 1 && *first != 0.0 => {
+                let change = (last - first) / first * 100.0;
+                let color = if change > 0.0 {
+                    Color::Green
+                } else if change < 0.0 {
+                    Color::Red
+                } else {
+                    Color::Reset
+                };
+                (format!("{:+.2}%", change), color)
+            }
+            _ => ("-".to_string(), Color::Reset),
+        };
+        let age = match row.updated {
+            Some(updated) => format_age(unix_now().saturating_sub(updated)),
+            None => "never".to_string(),
+        };
+        let name_style = if row.error.is_some() {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+
+        Row::new(vec![
+            Cell::from(row.name.clone()).style(name_style),
+            Cell::from(row.price_text.clone()),
+            Cell::from(change_text).style(Style::default().fg(change_color)),
+            Cell::from(sparkline(&row.history)),
+            Cell::from(age),
+            Cell::from(sources_line(&row.sources)),
+        ])
+    });
+
+    let table = Table::new(
+        table_rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(16),
+            Constraint::Length(9),
+            Constraint::Length(HISTORY_LEN as u16),
+            Constraint::Length(10),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(" Financial Data Fetcher "));
+    frame.render_widget(table, table_area);
+
+    let footer = Paragraph::new(format!(
+        " q: quit   change is over the trend window   cache hit rate: {:.0}%   warnings: {}",
+        cache::stats().hit_rate(),
+        LOG_PATH
+    ))
+    .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(footer, footer_area);
+}
+
+/// "12s ago", "5m ago", "3h ago" or "2d ago"
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..120 => format!("{}s ago", secs),
+        120..7200 => format!("{}m ago", secs / 60),
+        7200..172800 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Renders recent prices as a row of bar characters scaled between their min and max
+fn sparkline(history: &VecDeque<f64>) -> String {
+    let min = history.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = history.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    history
+        .iter()
+        .map(|price| {
+            if max > min {
+                let level = ((price - min) / (max - min) * (BARS.len() - 1) as f64).round();
+                BARS[level as usize]
+            } else {
+                BARS[BARS.len() / 2]  // Flat line
+            }
+        })
+        .collect()
+}
+
+/// Provider names coloured by their last result (yellow while their circuit is open)
+fn sources_line(sources: &[(String, SourceStatus)]) -> Line<'static> {
+    let spans: Vec<Span> = sources
+        .iter()
+        .flat_map(|(name, status)| {
+            let (style, label) = match status {
+                SourceStatus::Ok => (Style::default().fg(Color::Green), name.clone()),
+                SourceStatus::Failed(_) => (Style::default().fg(Color::Red), name.clone()),
+                SourceStatus::Untried => (Style::default().fg(Color::DarkGray), name.clone()),
+                // Skipped by its circuit breaker until the cool-down ends
+                SourceStatus::Open(_) => (Style::default().fg(Color::Yellow), format!("{} (open)", name)),
+            };
+            [Span::styled(label, style), Span::raw(" ")]
+        })
+        .collect();
+    Line::from(spans)
+}
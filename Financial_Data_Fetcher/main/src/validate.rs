@@ -0,0 +1,104 @@
+// Provider response validation: typed parsing that names the bad field, price sanity checks,
+// and a quarantine directory for bodies that fail either
+use serde::de::DeserializeOwned; // For parsing into each provider's response type
+use std::collections::hash_map::DefaultHasher; // For recognising a body already quarantined
+use std::collections::HashMap; // For the last quarantined body per provider
+use std::error::Error;   // For error handling
+use std::fs;             // For writing quarantined bodies
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;  // For quarantine file paths
+use std::sync::Mutex;    // Shared between the fetch thread and feed threads
+
+use crate::asset::unix_now;
+use crate::log::warning;
+
+/// Highest USD price accepted from any provider; anything above is a unit or parsing mix-up
+pub const MAX_PLAUSIBLE_PRICE: f64 = 1e9;
+
+/// Quarantine settings and what was saved last
+static QUARANTINE: Mutex<Quarantine> = Mutex::new(Quarantine { dir: None, saved: None });
+
+struct Quarantine {
+    dir: Option<PathBuf>,               // None until configured; bodies aren't saved before that
+    saved: Option<HashMap<String, u64>>, // Hash of the last body per provider, so one stuck
+                                        // on the same bad answer doesn't fill the disk
+}
+
+/// Sets the directory bad responses are saved to; call once at startup
+pub fn configure(dir: &str) {
+    lock().dir = Some(PathBuf::from(dir));
+}
+
+/// Parses a JSON body, naming the field that is missing or has the wrong type when it doesn't fit
+pub fn from_json<T: DeserializeOwned>(provider: &str, body: &str) -> Result<T, Box<dyn Error>> {
+    let deserializer = &mut serde_json::Deserializer::from_str(body);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let inner = e.into_inner();
+        let message = if inner.is_syntax() || inner.is_eof() {
+            format!("{} response is not valid JSON: {}", provider, inner)
+        } else if path == "." {
+            format!("{} response changed shape: {}", provider, inner)
+        } else {
+            format!("{} response changed shape at `{}`: {}", provider, path, inner)
+        };
+        message.into()
+    })
+}
+
+/// Parses a price the provider sends as a string, e.g. Coinbase's "amount": "76617.12"
+pub fn number(provider: &str, field: &str, text: &str) -> Result<f64, Box<dyn Error>> {
+    text.trim().parse().map_err(|_| {
+        format!("{} response changed shape at `{}`: expected a number, got {:?}", provider, field, text).into()
+    })
+}
+
+/// Rejects prices no market produces: NaN or infinite, zero or negative, or absurdly large
+pub fn check_price(provider: &str, price: f64) -> Result<f64, Box<dyn Error>> {
+    if !price.is_finite() {
+        Err(format!("{} returned a non-finite price ({})", provider, price).into())
+    } else if price <= 0.0 {
+        Err(format!("{} returned a non-positive price ({})", provider, price).into())
+    } else if price > MAX_PLAUSIBLE_PRICE {
+        Err(format!("{} returned an implausible price ({}, above {})", provider, price, MAX_PLAUSIBLE_PRICE).into())
+    } else {
+        Ok(price)
+    }
+}
+
+/// Saves a body that failed validation as "<dir>/<provider>-<symbol>-<unix time>.json" and
+/// returns the path. Nothing is saved if the provider's previous quarantined body was identical.
+pub fn quarantine(provider: &str, symbol: &str, body: &str) -> Option<PathBuf> {
+    let mut quarantine = lock();
+    let dir = quarantine.dir.clone()?;
+
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let hash = hasher.finish();
+    let saved = quarantine.saved.get_or_insert_with(HashMap::new);
+    if saved.get(provider) == Some(&hash) {
+        return None;
+    }
+
+    // Symbols like "^GSPC" or "ETH-USD" become file-name safe
+    let symbol: String = symbol
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let path = dir.join(format!("{}-{}-{}.json", provider, symbol, unix_now()));
+    let written = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, body));
+    match written {
+        Ok(()) => {
+            saved.insert(provider.to_string(), hash);
+            Some(path)
+        }
+        Err(e) => {
+            warning!("Error quarantining {} response to {}: {}", provider, path.display(), e);
+            None
+        }
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Quarantine> {
+    QUARANTINE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}